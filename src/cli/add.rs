@@ -2,9 +2,10 @@ use std::path::PathBuf;
 
 use anyhow::{Context, Result};
 
-use crate::model::ManifestEntry;
+use crate::model::{ManifestEntry, split_qualified};
 use crate::store::{ProjectStore, RepositoryStore};
 
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     data_dir: Option<&PathBuf>,
     name: String,
@@ -38,15 +39,18 @@ pub fn run(
             );
         }
 
+        // `team/react-docs` is recorded as `react-docs` from registry `team`
+        let (registry, bare) = split_qualified(&name);
         let entry = ManifestEntry {
             path,
             version,
             include,
             exclude,
+            registry: registry.map(str::to_string),
         };
 
         project
-            .add_reference(name.clone(), entry)
+            .add_reference(bare.to_string(), entry)
             .context("failed to add reference to manifest")?;
         println!("Added '{name}' to project manifest.");
     }
@@ -54,7 +58,8 @@ pub fn run(
     if sync {
         println!("Syncing...");
         drop(project);
-        crate::cli::sync::run(data_dir, Some(name), false)?;
+        let synced = if is_bundle { name } else { split_qualified(&name).1.to_string() };
+        crate::cli::sync::run(data_dir, Some(synced), false)?;
     } else {
         println!("Run `refstore sync` to fetch the content.");
     }
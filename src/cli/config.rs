@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 
 use crate::cli::ConfigSubcommand;
+use crate::model::config::{format_size, parse_size};
 use crate::store::RepositoryStore;
 
 pub fn run(data_dir: Option<&PathBuf>, cmd: ConfigSubcommand) -> Result<()> {
@@ -21,9 +22,22 @@ fn run_show(data_dir: Option<&PathBuf>) -> Result<()> {
     println!("Data directory: {}", repo.root().display());
     println!("MCP scope:      {}", config.mcp_scope);
     println!("Git depth:      {}", config.git_depth);
+    println!("Git cache:      {}", config.git_cache);
+    println!("Git backend:    {} ({})", config.git_backend, repo.git().name());
     if let Some(branch) = &config.default_branch {
         println!("Default branch: {branch}");
     }
+    println!("Dedup content:  {}", config.dedup_content);
+    println!("Max ref size:   {}", size_or_none(config.max_reference_size));
+    println!("Max file size:  {}", size_or_none(config.max_file_size));
+    println!("Binary files:   {}", config.binary_files);
+    let layers = repo.list_layers();
+    if !layers.is_empty() {
+        println!("Layers:");
+        for (name, path, _) in layers {
+            println!("  {name}: {}", path.display());
+        }
+    }
     Ok(())
 }
 
@@ -45,14 +59,31 @@ fn run_set(data_dir: Option<&PathBuf>, key: String, value: String) -> Result<()>
                 .parse::<u32>()
                 .with_context(|| format!("invalid git_depth value: {value} (expected a number)"))?;
         }
+        "git_cache" => {
+            config.git_cache = value
+                .parse::<bool>()
+                .with_context(|| format!("invalid git_cache value: {value} (expected true or false)"))?;
+        }
+        "git_backend" => config.git_backend = value.parse().map_err(anyhow::Error::msg)?,
         "default_branch" => {
-            config.default_branch = if value == "" || value == "none" {
+            config.default_branch = if value.is_empty() || value == "none" {
                 None
             } else {
                 Some(value.clone())
             };
         }
-        _ => anyhow::bail!("unknown config key: {key}\nValid keys: mcp_scope, git_depth, default_branch"),
+        "dedup_content" => {
+            config.dedup_content = value
+                .parse::<bool>()
+                .with_context(|| format!("invalid dedup_content value: {value} (expected true or false)"))?;
+        }
+        "max_reference_size" => config.max_reference_size = parse_limit(&value)?,
+        "max_file_size" => config.max_file_size = parse_limit(&value)?,
+        "binary_files" => config.binary_files = value.parse().map_err(anyhow::Error::msg)?,
+        _ => anyhow::bail!(
+            "unknown config key: {key}\nValid keys: mcp_scope, git_depth, git_cache, git_backend, default_branch, dedup_content, \
+             max_reference_size, max_file_size, binary_files"
+        ),
     }
 
     repo.save_config().context("failed to save config")?;
@@ -68,10 +99,28 @@ fn run_get(data_dir: Option<&PathBuf>, key: String) -> Result<()> {
     match key.as_str() {
         "mcp_scope" => println!("{}", config.mcp_scope),
         "git_depth" => println!("{}", config.git_depth),
+        "git_cache" => println!("{}", config.git_cache),
+        "git_backend" => println!("{}", config.git_backend),
         "default_branch" => {
             println!("{}", config.default_branch.as_deref().unwrap_or("(not set)"))
         }
+        "dedup_content" => println!("{}", config.dedup_content),
+        "max_reference_size" => println!("{}", size_or_none(config.max_reference_size)),
+        "max_file_size" => println!("{}", size_or_none(config.max_file_size)),
+        "binary_files" => println!("{}", config.binary_files),
         _ => anyhow::bail!("unknown config key: {key}"),
     }
     Ok(())
 }
+
+/// A size limit, or `none` to remove it.
+fn parse_limit(value: &str) -> Result<Option<u64>> {
+    if value.is_empty() || value == "none" {
+        return Ok(None);
+    }
+    parse_size(value).map(Some).map_err(anyhow::Error::msg)
+}
+
+fn size_or_none(limit: Option<u64>) -> String {
+    limit.map_or_else(|| "none".to_string(), format_size)
+}
@@ -5,7 +5,7 @@ use anyhow::{Context, Result};
 use crate::store::RepositoryStore;
 
 pub fn run(data_dir: Option<&PathBuf>, name: String) -> Result<()> {
-    let repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+    let repo = RepositoryStore::open_read_only(data_dir.map(|p| p.as_path()))
         .context("failed to open central repository")?;
 
     // Try as a reference first
@@ -14,13 +14,35 @@ pub fn run(data_dir: Option<&PathBuf>, name: String) -> Result<()> {
         println!("Name:        {}", reference.name);
         println!("Kind:        {}", reference.kind);
         println!("Source:      {}", reference.source);
+        if let Some(tracking) = reference
+            .source
+            .tracking_label(repo.config().default_branch.as_deref())
+        {
+            println!("Ref:         {tracking}");
+        }
+        if let crate::model::ReferenceSource::Git {
+            recurse_submodules,
+            lfs,
+            ..
+        } = &reference.source
+        {
+            if *recurse_submodules {
+                println!("Submodules:  recursive");
+            }
+            if let Some(lfs) = lfs {
+                println!("LFS:         {lfs}");
+            }
+        }
         if let Some(desc) = &reference.description {
             println!("Description: {desc}");
         }
         if !reference.tags.is_empty() {
             println!("Tags:        {}", reference.tags.join(", "));
         }
-        println!("Registry:    {}", resolved.registry_name);
+        match repo.layer_path(resolved.registry_name) {
+            Some(path) => println!("Layer:       {} ({})", resolved.registry_name, path.display()),
+            None => println!("Registry:    {}", resolved.registry_name),
+        }
         println!(
             "Added:       {}",
             reference.added_at.format("%Y-%m-%d %H:%M:%S UTC")
@@ -31,6 +53,13 @@ pub fn run(data_dir: Option<&PathBuf>, name: String) -> Result<()> {
         if let Some(checksum) = &reference.checksum {
             println!("Checksum:    {checksum}");
         }
+        if let Some(build) = &reference.build {
+            println!("Build:       `{}` -> {}", build.cmd, build.output.display());
+        }
+        if !reference.extract.is_empty() {
+            let formats: Vec<String> = reference.extract.iter().map(|f| f.to_string()).collect();
+            println!("Extract:     {}", formats.join(", "));
+        }
 
         if resolved.content_path.exists() {
             println!("Content:     {}", resolved.content_path.display());
@@ -4,10 +4,21 @@ use anyhow::{Context, Result};
 
 use crate::store::RepositoryStore;
 
-pub fn run(data_dir: Option<&PathBuf>, tag: Option<String>, kind: Option<String>) -> Result<()> {
-    let repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+pub fn run(data_dir: Option<&PathBuf>, tag: Option<String>, kind: Option<String>, shadowed: bool) -> Result<()> {
+    let repo = RepositoryStore::open_read_only(data_dir.map(|p| p.as_path()))
         .context("failed to open central repository")?;
 
+    if shadowed {
+        let names = repo.shadowed();
+        if names.is_empty() {
+            println!("No reference is defined in more than one registry.");
+        }
+        for (name, registries) in names {
+            println!("  {name}: {} wins over {}", registries[0], registries[1..].join(", "));
+        }
+        return Ok(());
+    }
+
     let refs = repo.list(tag.as_deref(), kind.as_deref());
 
     if refs.is_empty() {
@@ -35,7 +46,13 @@ pub fn run(data_dir: Option<&PathBuf>, tag: Option<String>, kind: Option<String>
             String::new()
         };
 
-        println!("  {}{} ({}){}{}", registry, r.name, r.kind, desc, tags);
+        let tracking = r
+            .source
+            .tracking_label(repo.config().default_branch.as_deref())
+            .map(|t| format!(", {t}"))
+            .unwrap_or_default();
+
+        println!("  {}{} ({}{}){}{}", registry, r.name, r.kind, tracking, desc, tags);
     }
     Ok(())
 }
@@ -6,6 +6,7 @@ pub mod init;
 pub mod install_mcp;
 pub mod list;
 pub mod mcp;
+pub mod outdated;
 pub mod registry;
 pub mod remove;
 pub mod search;
@@ -19,6 +20,8 @@ use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 
+use crate::model::{ExtractFormat, LfsMode};
+
 #[derive(Debug, Parser)]
 #[command(
     name = "refstore",
@@ -70,7 +73,8 @@ pub enum Command {
 
     /// Add a reference or bundle to the project manifest
     Add {
-        /// Name of the reference or bundle (must exist in central repository)
+        /// Name of the reference or bundle (must exist in central repository);
+        /// `registry/name` takes that registry's copy
         name: String,
 
         /// Add a bundle instead of a single reference
@@ -131,9 +135,13 @@ pub enum Command {
         #[arg(short, long)]
         tag: Option<String>,
 
-        /// Filter by kind (file, directory, git_repo)
+        /// Filter by kind (file, directory, git_repo, snippet)
         #[arg(short, long)]
         kind: Option<String>,
+
+        /// List names defined in more than one registry and which copy wins
+        #[arg(long, conflicts_with_all = ["tag", "kind"])]
+        shadowed: bool,
     },
 
     /// Search content across references
@@ -141,17 +149,20 @@ pub enum Command {
         /// Text to search for (case-insensitive)
         query: String,
 
-        /// Limit search to a specific reference
+        /// Limit search to a specific reference (`registry/name` for a registry's copy)
         #[arg(long = "ref")]
         reference: Option<String>,
     },
 
     /// Show detailed information about a reference or bundle
     Info {
-        /// Name of the reference or bundle
+        /// Name of the reference or bundle, optionally as `registry/name`
         name: String,
     },
 
+    /// Show pinned references with newer releases available
+    Outdated,
+
     /// Show version history for a reference
     Versions {
         /// Name of the reference
@@ -196,7 +207,8 @@ pub enum StoreSubcommand {
         /// Unique name for this reference
         name: String,
 
-        /// Source: file path, directory path, or git URL
+        /// Source: file path, directory path, git URL, or <scheme>::<address>
+        /// for a refstore-source-<scheme> helper
         source: String,
 
         /// Human-readable description
@@ -214,6 +226,82 @@ pub enum StoreSubcommand {
         /// Subdirectory within a git repo to use as root
         #[arg(long)]
         subpath: Option<PathBuf>,
+
+        /// Also check out git submodules (recursively)
+        #[arg(long)]
+        recurse_submodules: bool,
+
+        /// Git LFS handling: skip (keep pointer files) or fetch (download objects)
+        #[arg(long)]
+        lfs: Option<LfsMode>,
+
+        /// Extra argument passed to a source helper after the address (repeatable)
+        #[arg(long, allow_hyphen_values = true)]
+        plugin_arg: Vec<String>,
+
+        /// Generate Markdown siblings for these formats at fetch time (pdf, notebook)
+        #[arg(long)]
+        extract: Vec<ExtractFormat>,
+
+        /// Shell command run on a scratch checkout at fetch time (e.g. "mdbook build")
+        #[arg(long, requires = "build_output")]
+        build_cmd: Option<String>,
+
+        /// File or directory produced by --build-cmd; only this is stored
+        #[arg(long, requires = "build_cmd")]
+        build_output: Option<PathBuf>,
+
+        /// Commit even if the content looks like it contains credentials
+        #[arg(long)]
+        allow_secrets: bool,
+
+        /// Ignore the configured size limits and binary-file policy
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Add an inline text snippet to the local store (opens $EDITOR unless --text or --from-stdin)
+    AddSnippet {
+        /// Unique name for this snippet
+        name: String,
+
+        /// Snippet text
+        #[arg(long, conflicts_with = "from_stdin")]
+        text: Option<String>,
+
+        /// Read snippet text from stdin
+        #[arg(long)]
+        from_stdin: bool,
+
+        /// Human-readable description
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Tags for organization
+        #[arg(short, long)]
+        tag: Vec<String>,
+
+        /// Commit even if the text looks like it contains credentials
+        #[arg(long)]
+        allow_secrets: bool,
+    },
+
+    /// Edit the text of a snippet (opens $EDITOR unless --text or --from-stdin)
+    EditSnippet {
+        /// Name of the snippet to edit
+        name: String,
+
+        /// Replacement text
+        #[arg(long, conflicts_with = "from_stdin")]
+        text: Option<String>,
+
+        /// Read replacement text from stdin
+        #[arg(long)]
+        from_stdin: bool,
+
+        /// Commit even if the text looks like it contains credentials
+        #[arg(long)]
+        allow_secrets: bool,
     },
 
     /// Remove a reference from the local store
@@ -230,6 +318,14 @@ pub enum StoreSubcommand {
     Update {
         /// Name of the reference to update (omit for all)
         name: Option<String>,
+
+        /// Commit even if the content looks like it contains credentials
+        #[arg(long)]
+        allow_secrets: bool,
+
+        /// Ignore the configured size limits and binary-file policy
+        #[arg(short, long)]
+        force: bool,
     },
 
     /// Tag the current state of the registry for version pinning
@@ -245,6 +341,21 @@ pub enum StoreSubcommand {
     /// List tags on the registry
     Tags,
 
+    /// Release the current content of a reference as a semantic version
+    /// (recorded as the tag <name>/v<version>)
+    Release {
+        /// Name of the reference
+        name: String,
+
+        /// Semantic version, e.g. 1.2.0
+        #[arg(value_name = "VERSION")]
+        semver: String,
+
+        /// Release notes stored in the tag
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+
     /// Push a reference from the local store to another registry
     Push {
         /// Name of the reference to push
@@ -253,7 +364,32 @@ pub enum StoreSubcommand {
         /// Path to the target registry
         #[arg(long)]
         to: PathBuf,
+
+        /// Commit even if the content looks like it contains credentials
+        #[arg(long)]
+        allow_secrets: bool,
+
+        /// Ignore the configured size limits and binary-file policy
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Show recent operations on the local store
+    History {
+        /// Maximum number of operations to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Undo the last store operation, or restore the store to an earlier commit
+    Undo {
+        /// Commit hash (from `store history`) to restore the store to
+        #[arg(long)]
+        to: Option<String>,
     },
+
+    /// Deduplicate content into the object store and prune unused objects
+    Gc,
 }
 
 #[derive(Debug, Subcommand)]
@@ -329,6 +465,25 @@ pub enum RegistrySubcommand {
         name: String,
         /// Git URL of the registry repository
         url: String,
+        /// Tag, branch, or commit to pin the registry to (default: track the default branch)
+        #[arg(long = "ref")]
+        git_ref: Option<String>,
+    },
+
+    /// Pin a remote registry to a tag, branch, or commit
+    Pin {
+        /// Name of the registry to pin
+        name: String,
+        /// Tag, branch, or commit to check out
+        rev: String,
+    },
+
+    /// Set the order registries are searched in
+    Reorder {
+        /// Every remote registry, first to last; include `local` to place the
+        /// local store (default: first)
+        #[arg(required = true)]
+        names: Vec<String>,
     },
 
     /// Remove a remote registry
@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use semver::VersionReq;
+
+use crate::store::release;
+use crate::store::{ProjectStore, RepositoryStore};
+
+pub fn run(data_dir: Option<&PathBuf>) -> Result<()> {
+    let repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+        .context("failed to open central repository")?;
+    let project = ProjectStore::open(None).context("failed to open project")?;
+
+    let resolved = project
+        .resolve_all_references(&repo)
+        .context("failed to resolve references")?;
+
+    let mut outdated = Vec::new();
+    for (name, entry) in &resolved {
+        let Some(pin) = &entry.version else {
+            continue;
+        };
+        let lookup = entry.lookup_name(name);
+        let current = match repo.resolve_version(&lookup, pin) {
+            Ok(r) => r.version,
+            Err(e) => {
+                eprintln!("warning: {e}");
+                continue;
+            }
+        };
+        // Pins to a commit or a non-semver tag have nothing to compare against
+        let Some(current) = current else {
+            continue;
+        };
+        let candidates = repo
+            .version_candidates(&lookup)
+            .with_context(|| format!("failed to list versions of '{lookup}'"))?;
+
+        // An exact pin is compatible with the same caret range cargo would use
+        let req = if release::is_range(pin) {
+            VersionReq::parse(pin)?
+        } else {
+            VersionReq::parse(&format!("^{current}"))?
+        };
+        let compatible = release::highest_match(&candidates, &req)
+            .map(|c| &c.version)
+            .filter(|v| **v > current);
+        let latest = candidates
+            .first()
+            .map(|c| &c.version)
+            .filter(|v| **v > current && !req.matches(v));
+
+        let mut parts = vec![format!("using {current}")];
+        if let Some(v) = compatible {
+            parts.push(format!("compatible {v}"));
+        }
+        if let Some(v) = latest {
+            parts.push(format!("latest {v} (incompatible)"));
+        }
+        if parts.len() > 1 {
+            outdated.push(format!("  {name} ({pin}): {}", parts.join(", ")));
+        }
+    }
+
+    if outdated.is_empty() {
+        println!("All pinned references are up to date.");
+        return Ok(());
+    }
+
+    println!("Outdated references:");
+    for line in &outdated {
+        println!("{line}");
+    }
+    Ok(())
+}
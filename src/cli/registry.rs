@@ -3,15 +3,33 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 
 use super::RegistrySubcommand;
+use crate::model::GitBackendKind;
 use crate::store::{RegistryStore, RepositoryStore};
 
 pub fn run(data_dir: Option<&PathBuf>, cmd: RegistrySubcommand) -> Result<()> {
     match cmd {
-        RegistrySubcommand::Add { name, url } => {
+        RegistrySubcommand::Add { name, url, git_ref } => {
             let mut repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
                 .context("failed to open central repository")?;
-            repo.add_registry(&name, &url)?;
-            println!("Added registry '{name}' from {url}");
+            repo.add_registry(&name, &url, git_ref.as_deref())?;
+            match git_ref {
+                Some(rev) => println!("Added registry '{name}' from {url}, pinned to {rev}"),
+                None => println!("Added registry '{name}' from {url}"),
+            }
+            Ok(())
+        }
+        RegistrySubcommand::Pin { name, rev } => {
+            let mut repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+                .context("failed to open central repository")?;
+            repo.pin_registry(&name, &rev)?;
+            println!("Pinned registry '{name}' to {rev}");
+            Ok(())
+        }
+        RegistrySubcommand::Reorder { names } => {
+            let mut repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+                .context("failed to open central repository")?;
+            repo.reorder_registries(&names)?;
+            println!("Registries are now searched in this order: {}", repo.resolution_order().join(", "));
             Ok(())
         }
         RegistrySubcommand::Remove { name, force: _ } => {
@@ -25,20 +43,38 @@ pub fn run(data_dir: Option<&PathBuf>, cmd: RegistrySubcommand) -> Result<()> {
             let repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
                 .context("failed to open central repository")?;
 
+            // Listed in resolution order
+            let remotes = repo.list_registries();
+            let ahead = remotes
+                .iter()
+                .take_while(|(name, _)| repo.config().registry_priority(name) > 0)
+                .count();
+            for (name, store) in &remotes[..ahead] {
+                print_remote(&repo, name, store)?;
+            }
+
             let local = repo.local_registry();
             let local_refs = local.list(None, None);
             let local_bundles = local.list_bundles(None);
             println!("local: {} references, {} bundles", local_refs.len(), local_bundles.len());
 
-            let remotes = repo.list_registries();
+            for (name, path, store) in repo.list_layers() {
+                let refs = store.list(None, None);
+                let bundles = store.list_bundles(None);
+                println!(
+                    "{name} (layer, {}): {} references, {} bundles",
+                    path.display(),
+                    refs.len(),
+                    bundles.len()
+                );
+            }
+
             if remotes.is_empty() {
                 println!("\nNo remote registries configured.");
                 println!("Add one with: refstore registry add <name> <git-url>");
             } else {
-                for (name, store) in &remotes {
-                    let refs = store.list(None, None);
-                    let bundles = store.list_bundles(None);
-                    println!("{name}: {} references, {} bundles", refs.len(), bundles.len());
+                for (name, store) in &remotes[ahead..] {
+                    print_remote(&repo, name, store)?;
                 }
             }
             Ok(())
@@ -59,7 +95,8 @@ pub fn run(data_dir: Option<&PathBuf>, cmd: RegistrySubcommand) -> Result<()> {
             Ok(())
         }
         RegistrySubcommand::Init { path } => {
-            RegistryStore::init_new(&path)
+            let git = crate::git::select(GitBackendKind::default())?;
+            RegistryStore::init_new(&path, &*git)
                 .with_context(|| format!("failed to initialize registry at {}", path.display()))?;
             println!("Initialized registry at {}", path.display());
             println!("Add references with: refstore --data-dir {} store add <name> <source>", path.display());
@@ -67,3 +104,22 @@ pub fn run(data_dir: Option<&PathBuf>, cmd: RegistrySubcommand) -> Result<()> {
         }
     }
 }
+
+fn print_remote(repo: &RepositoryStore, name: &str, store: &RegistryStore) -> Result<()> {
+    let refs = store.list(None, None);
+    let bundles = store.list_bundles(None);
+    let pin = match repo.registry_pin(name)? {
+        Some(rev) => format!(", pinned to {rev}"),
+        None => String::new(),
+    };
+    let priority = match repo.config().registry_priority(name) {
+        0 => String::new(),
+        p => format!(", priority {p}"),
+    };
+    println!("{name}: {} references, {} bundles{pin}{priority}", refs.len(), bundles.len());
+    let newer = repo.newer_registry_revs(name)?;
+    if !newer.is_empty() {
+        println!("  newer: {}", newer.join(", "));
+    }
+    Ok(())
+}
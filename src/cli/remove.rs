@@ -1,8 +1,9 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 
+use crate::model::split_qualified;
 use crate::store::{ProjectStore, RepositoryStore};
 
 pub fn run(data_dir: Option<&PathBuf>, name: String, is_bundle: bool, purge: bool) -> Result<()> {
@@ -17,16 +18,12 @@ pub fn run(data_dir: Option<&PathBuf>, name: String, is_bundle: bool, purge: boo
             if let Some(bundle) = repo.get_bundle(&name) {
                 let refs_dir = project.references_dir();
                 for ref_name in &bundle.references {
+                    let ref_name = split_qualified(ref_name).1;
                     if project.manifest().references.contains_key(ref_name) {
                         continue; // skip refs explicitly in manifest
                     }
-                    let ref_dir = refs_dir.join(ref_name);
-                    if ref_dir.exists() {
-                        fs::remove_dir_all(&ref_dir).with_context(|| {
-                            format!("failed to purge {}", ref_dir.display())
-                        })?;
-                        println!("Purged content from {}", ref_dir.display());
-                    }
+                    purge_path(&refs_dir.join(ref_name))?;
+                    purge_path(&refs_dir.join(format!("{ref_name}.md")))?;
                 }
             }
         }
@@ -36,19 +33,30 @@ pub fn run(data_dir: Option<&PathBuf>, name: String, is_bundle: bool, purge: boo
             .context("failed to remove bundle from manifest")?;
         println!("Removed bundle '{name}' from project manifest.");
     } else {
+        let name = split_qualified(&name).1;
         project
-            .remove_reference(&name)
+            .remove_reference(name)
             .context("failed to remove reference from manifest")?;
         println!("Removed '{name}' from project manifest.");
 
         if purge {
-            let ref_dir = project.references_dir().join(&name);
-            if ref_dir.exists() {
-                fs::remove_dir_all(&ref_dir)
-                    .with_context(|| format!("failed to purge {}", ref_dir.display()))?;
-                println!("Purged content from {}", ref_dir.display());
-            }
+            let refs_dir = project.references_dir();
+            purge_path(&refs_dir.join(name))?;
+            purge_path(&refs_dir.join(format!("{name}.md")))?;
         }
     }
     Ok(())
 }
+
+/// Delete synced content: a reference directory, or the `<name>.md` file of a snippet.
+fn purge_path(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path).with_context(|| format!("failed to purge {}", path.display()))?;
+    } else if path.is_file() {
+        fs::remove_file(path).with_context(|| format!("failed to purge {}", path.display()))?;
+    } else {
+        return Ok(());
+    }
+    println!("Purged content from {}", path.display());
+    Ok(())
+}
@@ -5,7 +5,7 @@ use anyhow::{Context, Result};
 use crate::store::RepositoryStore;
 
 pub fn run(data_dir: Option<&PathBuf>, query: String, reference: Option<String>) -> Result<()> {
-    let repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+    let repo = RepositoryStore::open_read_only(data_dir.map(|p| p.as_path()))
         .context("failed to open central repository")?;
 
     let refs = match &reference {
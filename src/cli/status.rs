@@ -3,6 +3,7 @@ use std::path::PathBuf;
 
 use anyhow::{Context, Result};
 
+use crate::model::split_qualified;
 use crate::store::{ProjectStore, RepositoryStore};
 
 pub fn run(data_dir: Option<&PathBuf>) -> Result<()> {
@@ -49,7 +50,7 @@ pub fn run(data_dir: Option<&PathBuf>) -> Result<()> {
         if let Some(b) = repo.get_bundle(bundle_name) {
             for ref_name in &b.references {
                 ref_to_bundle
-                    .entry(ref_name.clone())
+                    .entry(split_qualified(ref_name).1.to_string())
                     .or_insert_with(|| bundle_name.clone());
             }
         }
@@ -57,10 +58,8 @@ pub fn run(data_dir: Option<&PathBuf>) -> Result<()> {
 
     println!("References:");
     for (name, entry) in &resolved {
-        let target_dir = match &entry.path {
-            Some(p) => refs_dir.join(p),
-            None => refs_dir.join(name),
-        };
+        let lookup = entry.lookup_name(name);
+        let target_dir = project.target_path(name, entry, repo.get(&lookup).map(|r| &r.kind));
 
         let status = if target_dir.exists() {
             if crate::git::is_git_repo(&target_dir) {
@@ -83,7 +82,7 @@ pub fn run(data_dir: Option<&PathBuf>) -> Result<()> {
 
         let source = if manifest.references.contains_key(name) {
             // Show registry origin if not from local
-            match repo.resolve(name) {
+            match repo.resolve(&lookup) {
                 Some(r) if r.registry_name != "local" => {
                     format!(" (registry: {})", r.registry_name)
                 }
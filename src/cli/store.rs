@@ -1,12 +1,15 @@
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
 use chrono::Utc;
 
 use crate::cli::StoreSubcommand;
-use crate::model::{Reference, ReferenceKind, ReferenceSource};
+use crate::model::{BuildStep, ExtractFormat, LfsMode, Reference, ReferenceKind, ReferenceSource};
 use crate::store::RepositoryStore;
+use crate::store::quota::ContentReport;
+use crate::store::release;
+use crate::store::repository::ContentChecks;
 
 pub fn run(data_dir: Option<&PathBuf>, cmd: StoreSubcommand) -> Result<()> {
     match cmd {
@@ -17,28 +20,88 @@ pub fn run(data_dir: Option<&PathBuf>, cmd: StoreSubcommand) -> Result<()> {
             tag,
             git_ref,
             subpath,
-        } => run_add(data_dir, name, source, description, tag, git_ref, subpath),
+            recurse_submodules,
+            lfs,
+            plugin_arg,
+            extract,
+            build_cmd,
+            build_output,
+            allow_secrets,
+            force,
+        } => run_add(
+            data_dir,
+            name,
+            source,
+            description,
+            tag,
+            SourceOptions {
+                git_ref,
+                subpath,
+                recurse_submodules,
+                lfs,
+                plugin_args: plugin_arg,
+            },
+            extract,
+            build_cmd
+                .zip(build_output)
+                .map(|(cmd, output)| BuildStep { cmd, output }),
+            ContentChecks { allow_secrets, force },
+        ),
+        StoreSubcommand::AddSnippet {
+            name,
+            text,
+            from_stdin,
+            description,
+            tag,
+            allow_secrets,
+        } => run_add_snippet(data_dir, name, text, from_stdin, description, tag, allow_secrets),
+        StoreSubcommand::EditSnippet {
+            name,
+            text,
+            from_stdin,
+            allow_secrets,
+        } => run_edit_snippet(data_dir, name, text, from_stdin, allow_secrets),
         StoreSubcommand::Remove { name, force } => run_remove(data_dir, name, force),
-        StoreSubcommand::Update { name } => run_update(data_dir, name),
+        StoreSubcommand::Update {
+            name,
+            allow_secrets,
+            force,
+        } => run_update(data_dir, name, ContentChecks { allow_secrets, force }),
         StoreSubcommand::Tag { name, message } => run_tag(data_dir, name, message),
         StoreSubcommand::Tags => run_tags(data_dir),
-        StoreSubcommand::Push { name, to } => run_push(data_dir, name, to),
+        StoreSubcommand::Release {
+            name,
+            semver,
+            message,
+        } => run_release(data_dir, name, semver, message),
+        StoreSubcommand::Push {
+            name,
+            to,
+            allow_secrets,
+            force,
+        } => run_push(data_dir, name, to, ContentChecks { allow_secrets, force }),
+        StoreSubcommand::History { limit } => run_history(data_dir, limit),
+        StoreSubcommand::Undo { to } => run_undo(data_dir, to),
+        StoreSubcommand::Gc => run_gc(data_dir),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_add(
     data_dir: Option<&PathBuf>,
     name: String,
     source: String,
     description: Option<String>,
     tags: Vec<String>,
-    git_ref: Option<String>,
-    subpath: Option<PathBuf>,
+    options: SourceOptions,
+    extract: Vec<ExtractFormat>,
+    build: Option<BuildStep>,
+    checks: ContentChecks,
 ) -> Result<()> {
     let mut repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
         .context("failed to open central repository")?;
 
-    let (kind, ref_source) = parse_source(&source, git_ref, subpath)?;
+    let (kind, ref_source) = parse_source(&source, options)?;
 
     let reference = Reference {
         name: name.clone(),
@@ -49,16 +112,131 @@ fn run_add(
         added_at: Utc::now(),
         last_synced: Some(Utc::now()),
         checksum: None,
+        extract,
+        build,
     };
 
-    repo.add(reference)
+    let report = repo
+        .add(reference, checks)
         .context("failed to add reference to repository")?;
 
     println!("Added '{name}' to central repository.");
+    print_content_report(&report, "");
     println!("Content cached at: {}", repo.content_path(&name).display());
     Ok(())
 }
 
+fn run_add_snippet(
+    data_dir: Option<&PathBuf>,
+    name: String,
+    text: Option<String>,
+    from_stdin: bool,
+    description: Option<String>,
+    tags: Vec<String>,
+    allow_secrets: bool,
+) -> Result<()> {
+    let mut repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+        .context("failed to open central repository")?;
+
+    let text = read_snippet_text(text, from_stdin, "")?;
+
+    let reference = Reference {
+        name: name.clone(),
+        kind: ReferenceKind::Snippet,
+        source: ReferenceSource::Inline,
+        description,
+        tags,
+        added_at: Utc::now(),
+        last_synced: Some(Utc::now()),
+        checksum: None,
+        extract: Vec::new(),
+        build: None,
+    };
+
+    repo.add_snippet(reference, &text, allow_secrets)
+        .context("failed to add snippet to repository")?;
+
+    println!("Added snippet '{name}' to central repository.");
+    Ok(())
+}
+
+fn run_edit_snippet(
+    data_dir: Option<&PathBuf>,
+    name: String,
+    text: Option<String>,
+    from_stdin: bool,
+    allow_secrets: bool,
+) -> Result<()> {
+    let mut repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+        .context("failed to open central repository")?;
+
+    let current = repo.snippet_text(&name)?;
+    let text = read_snippet_text(text, from_stdin, &current)?;
+
+    if text == current {
+        println!("Snippet '{name}' unchanged.");
+        return Ok(());
+    }
+
+    repo.edit_snippet(&name, &text, allow_secrets)
+        .context("failed to update snippet")?;
+
+    println!("Updated snippet '{name}'.");
+    Ok(())
+}
+
+/// Get snippet text from `--text`, stdin, or an editor session seeded with `initial`.
+fn read_snippet_text(text: Option<String>, from_stdin: bool, initial: &str) -> Result<String> {
+    let text = if let Some(t) = text {
+        t
+    } else if from_stdin {
+        let mut buf = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .context("failed to read snippet from stdin")?;
+        buf
+    } else {
+        edit_in_editor(initial)?
+    };
+
+    if text.trim().is_empty() {
+        anyhow::bail!("snippet text is empty");
+    }
+    Ok(text)
+}
+
+fn edit_in_editor(initial: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // Closed before the editor runs, and deleted on drop
+    let path = tempfile::Builder::new()
+        .prefix("refstore-snippet-")
+        .suffix(".md")
+        .tempfile()
+        .context("failed to create a temporary file")?
+        .into_temp_path();
+    std::fs::write(&path, initial)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    // $EDITOR may carry arguments (e.g. "code --wait")
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("failed to launch editor '{editor}'"));
+
+    match status {
+        Ok(s) if s.success() => std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display())),
+        Ok(s) => Err(anyhow::anyhow!("editor '{editor}' exited with {s}")),
+        Err(e) => Err(e),
+    }
+}
+
 fn run_remove(data_dir: Option<&PathBuf>, name: String, force: bool) -> Result<()> {
     let mut repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
         .context("failed to open central repository")?;
@@ -89,13 +267,18 @@ fn run_remove(data_dir: Option<&PathBuf>, name: String, force: bool) -> Result<(
     Ok(())
 }
 
-fn run_update(data_dir: Option<&PathBuf>, name: Option<String>) -> Result<()> {
+fn run_update(data_dir: Option<&PathBuf>, name: Option<String>, checks: ContentChecks) -> Result<()> {
     let mut repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
         .context("failed to open central repository")?;
 
     let names: Vec<String> = match name {
         Some(n) => vec![n],
-        None => repo.list(None, None).iter().map(|r| r.reference.name.clone()).collect(),
+        None => repo
+            .list(None, None)
+            .iter()
+            .filter(|r| r.reference.kind != ReferenceKind::Snippet)
+            .map(|r| r.reference.name.clone())
+            .collect(),
     };
 
     if names.is_empty() {
@@ -104,15 +287,33 @@ fn run_update(data_dir: Option<&PathBuf>, name: Option<String>) -> Result<()> {
     }
 
     let mut updated = 0;
+    let mut skipped = 0;
     let mut failed = 0;
 
     for ref_name in &names {
+        // Tags and commits never move upstream, so there is nothing to re-fetch
+        if let Some(r) = repo.get(ref_name)
+            && r.source.is_fixed_ref()
+        {
+            let label = r.source.tracking_label(None).unwrap_or_default();
+            println!("  {ref_name}: skipped ({label})");
+            skipped += 1;
+            continue;
+        }
+
         print!("  {ref_name}: updating... ");
         std::io::stdout().flush()?;
 
-        match repo.update(ref_name) {
-            Ok(()) => {
-                println!("done");
+        match repo.update(ref_name, checks) {
+            Ok(summary) => {
+                println!("done ({})", summary.short_stat());
+                if let Some(range) = summary.upstream_range() {
+                    println!("    upstream: {range}");
+                }
+                for line in summary.file_lines() {
+                    println!("    {line}");
+                }
+                print_content_report(&summary.content, "    ");
                 updated += 1;
             }
             Err(e) => {
@@ -122,7 +323,7 @@ fn run_update(data_dir: Option<&PathBuf>, name: Option<String>) -> Result<()> {
         }
     }
 
-    println!("\nUpdate complete: {updated} updated, {failed} failed");
+    println!("\nUpdate complete: {updated} updated, {skipped} skipped, {failed} failed");
     Ok(())
 }
 
@@ -156,22 +357,149 @@ fn run_tags(data_dir: Option<&PathBuf>) -> Result<()> {
     Ok(())
 }
 
-fn run_push(data_dir: Option<&PathBuf>, name: String, to: PathBuf) -> Result<()> {
+fn run_release(
+    data_dir: Option<&PathBuf>,
+    name: String,
+    version: String,
+    message: Option<String>,
+) -> Result<()> {
     let repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
         .context("failed to open central repository")?;
 
-    repo.push_to(&name, &to)
+    let parsed = release::parse_version(&version)
+        .with_context(|| format!("invalid version '{version}' (expected semver like 1.2.0)"))?;
+    let tag = repo
+        .release(&name, &parsed, message.as_deref())
+        .with_context(|| format!("failed to release '{name}'"))?;
+
+    println!("Released '{name}' {parsed} (tag {tag}).");
+    println!("Use --pin {parsed} when adding '{name}' to a project to pin to this release.");
+    Ok(())
+}
+
+fn run_push(data_dir: Option<&PathBuf>, name: String, to: PathBuf, checks: ContentChecks) -> Result<()> {
+    let repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+        .context("failed to open central repository")?;
+
+    let report = repo
+        .push_to(&name, &to, checks)
         .with_context(|| format!("failed to push '{name}' to {}", to.display()))?;
 
     println!("Pushed '{name}' to {}", to.display());
+    print_content_report(&report, "");
+    Ok(())
+}
+
+/// List binary files the `binary_files` policy left out or let through.
+fn print_content_report(report: &ContentReport, indent: &str) {
+    for path in &report.skipped {
+        println!("{indent}skipped binary file: {}", path.display());
+    }
+    for path in &report.binary {
+        eprintln!("{indent}warning: stored binary file: {}", path.display());
+    }
+}
+
+fn run_history(data_dir: Option<&PathBuf>, limit: usize) -> Result<()> {
+    let repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+        .context("failed to open central repository")?;
+
+    let entries = repo.history(limit).context("failed to read store history")?;
+    if entries.is_empty() {
+        println!("No store history.");
+        return Ok(());
+    }
+
+    println!("Store history (newest first):");
+    println!();
+    for entry in &entries {
+        let what = match &entry.operation {
+            Some(op) => match &op.detail {
+                Some(detail) => format!("{:<6} {:<9} {} ({detail})", op.action, op.target, op.name),
+                None => format!("{:<6} {:<9} {}", op.action, op.target, op.name),
+            },
+            None => entry.log.message.clone(),
+        };
+        println!("  {} {} {}", entry.log.hash, entry.log.date, what);
+    }
+    println!();
+    println!("Tip: use `refstore store undo --to <hash>` to restore the store to that point.");
+    Ok(())
+}
+
+fn run_undo(data_dir: Option<&PathBuf>, to: Option<String>) -> Result<()> {
+    let mut repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+        .context("failed to open central repository")?;
+
+    let restored = repo.undo(to.as_deref()).context("failed to undo")?;
+
+    println!("Restored store to {:.8}.", restored);
+    println!("Run `refstore store undo` again to reverse this.");
     Ok(())
 }
 
-fn parse_source(
-    source: &str,
+fn run_gc(data_dir: Option<&PathBuf>) -> Result<()> {
+    let repo = RepositoryStore::open(data_dir.map(|p| p.as_path()))
+        .context("failed to open central repository")?;
+
+    let stats = repo.gc().context("failed to collect garbage")?;
+
+    if repo.config().dedup_content {
+        println!(
+            "Linked {} files ({} newly deduplicated, {} bytes saved).",
+            stats.files, stats.deduplicated, stats.bytes_saved
+        );
+        println!("Object store: {} bytes.", stats.object_bytes);
+    } else {
+        println!("dedup_content is off; content files are plain copies.");
+    }
+    println!("Pruned {} unused objects.", stats.pruned);
+    if stats.versions_pruned > 0 {
+        println!("Dropped {} cached versions of removed references.", stats.versions_pruned);
+    }
+    Ok(())
+}
+
+/// `store add` options that only apply to some kinds of source.
+struct SourceOptions {
     git_ref: Option<String>,
     subpath: Option<PathBuf>,
-) -> Result<(ReferenceKind, ReferenceSource)> {
+    recurse_submodules: bool,
+    lfs: Option<LfsMode>,
+    plugin_args: Vec<String>,
+}
+
+impl SourceOptions {
+    fn has_git_options(&self) -> bool {
+        self.git_ref.is_some() || self.subpath.is_some() || self.recurse_submodules || self.lfs.is_some()
+    }
+}
+
+fn parse_source(source: &str, options: SourceOptions) -> Result<(ReferenceKind, ReferenceSource)> {
+    // `<scheme>::<address>`, as with git remote helpers
+    if let Some((scheme, address)) = source.split_once("::")
+        && !scheme.is_empty()
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        if options.has_git_options() {
+            anyhow::bail!("--ref, --subpath, --recurse-submodules and --lfs only apply to git sources");
+        }
+        let mut args = vec![address.to_string()];
+        args.extend(options.plugin_args);
+        return Ok((
+            ReferenceKind::Directory,
+            ReferenceSource::Plugin {
+                scheme: scheme.to_string(),
+                args,
+            },
+        ));
+    }
+    if !options.plugin_args.is_empty() {
+        anyhow::bail!("--plugin-arg only applies to <scheme>::<address> sources");
+    }
+
     if source.starts_with("https://")
         || source.starts_with("http://")
         || source.starts_with("git@")
@@ -182,12 +510,19 @@ fn parse_source(
             ReferenceKind::GitRepo,
             ReferenceSource::Git {
                 url: source.to_string(),
-                r#ref: git_ref,
-                subpath,
+                r#ref: options.git_ref,
+                subpath: options.subpath,
+                ref_kind: None,
+                recurse_submodules: options.recurse_submodules,
+                lfs: options.lfs,
             },
         ));
     }
 
+    if options.recurse_submodules || options.lfs.is_some() {
+        anyhow::bail!("--recurse-submodules and --lfs only apply to git sources");
+    }
+
     let path = PathBuf::from(source);
     let path = if path.is_relative() {
         std::env::current_dir().unwrap_or_default().join(&path)
@@ -3,7 +3,7 @@ use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 use globset::{Glob, GlobSetBuilder};
 
-use crate::model::ManifestEntry;
+use crate::model::{ManifestEntry, ReferenceKind, SyncedVersion, split_qualified};
 use crate::store::{ProjectStore, RepositoryStore};
 
 pub fn run(data_dir: Option<&PathBuf>, name: Option<String>, force: bool) -> Result<()> {
@@ -22,10 +22,11 @@ pub fn run(data_dir: Option<&PathBuf>, name: Option<String>, force: bool) -> Res
 
     let entries: Vec<_> = match &name {
         Some(n) => {
+            let n = split_qualified(n).1;
             let entry = resolved
                 .get(n)
                 .ok_or_else(|| anyhow::anyhow!("reference '{n}' not found in project manifest (including bundle-expanded references)"))?;
-            vec![(n.as_str(), entry)]
+            vec![(n, entry)]
         }
         None => resolved.iter().map(|(k, v)| (k.as_str(), v)).collect(),
     };
@@ -35,43 +36,64 @@ pub fn run(data_dir: Option<&PathBuf>, name: Option<String>, force: bool) -> Res
         return Ok(());
     }
 
+    let mut state = project.sync_state();
+    if name.is_none() {
+        // Forget entries that are no longer in the manifest
+        state.references.retain(|n, _| resolved.contains_key(n));
+    }
+
     let mut synced = 0;
     let mut failed = 0;
 
     for (ref_name, entry) in &entries {
-        let reference = match repo.get(ref_name) {
+        let lookup = entry.lookup_name(ref_name);
+        let reference = match repo.get(&lookup) {
             Some(r) => r,
             None => {
-                eprintln!("warning: '{ref_name}' not found in central repository, skipping");
+                eprintln!("warning: '{lookup}' not found in central repository, skipping");
                 failed += 1;
                 continue;
             }
         };
-        let _ = reference; // used for future metadata; currently we only need the content path
+        let is_snippet = reference.kind == ReferenceKind::Snippet;
+        let target_dir = project.target_path(ref_name, entry, Some(&reference.kind));
 
-        let target_dir = match &entry.path {
-            Some(p) => refs_dir.join(p),
-            None => refs_dir.join(ref_name),
+        // If version is pinned, resolve it to a commit in the owning registry
+        let resolved = match &entry.version {
+            Some(version) => match repo.resolve_version(&lookup, version) {
+                Ok(r) => Some(r),
+                Err(e) => {
+                    eprintln!("  {ref_name}: FAILED - {e}");
+                    state.references.remove(*ref_name);
+                    failed += 1;
+                    continue;
+                }
+            },
+            None => None,
         };
 
-        // If version is pinned, extract content from that specific git ref
-        let versioned_source = if let Some(version) = &entry.version {
-            match repo.content_at_version(ref_name, version) {
-                Ok(path) => Some(path),
+        if let Some(resolved) = &resolved
+            && target_dir.exists()
+            && !force
+            && state.references.get(*ref_name) == Some(&SyncedVersion::new(&resolved.commit, entry))
+        {
+            println!("  {ref_name}: up to date ({:.8})", resolved.commit);
+            synced += 1;
+            continue;
+        }
+
+        let source_dir = if let Some(resolved) = &resolved {
+            match repo.content_at_version(&lookup, resolved) {
+                Ok(path) => path,
                 Err(e) => {
                     eprintln!("  {ref_name}: FAILED - {e}");
+                    state.references.remove(*ref_name);
                     failed += 1;
                     continue;
                 }
             }
         } else {
-            None
-        };
-
-        let source_dir = if let Some(ref versioned) = versioned_source {
-            versioned.clone()
-        } else {
-            match repo.resolve_content_path(ref_name) {
+            match repo.resolve_content_path(&lookup) {
                 Some(p) if p.exists() => p,
                 _ => {
                     eprintln!("warning: no cached content for '{ref_name}', skipping");
@@ -80,8 +102,14 @@ pub fn run(data_dir: Option<&PathBuf>, name: Option<String>, force: bool) -> Res
                 }
             }
         };
+        // A snippet syncs as its single Markdown file rather than a directory
+        let source_dir = if is_snippet {
+            source_dir.join(format!("{ref_name}.md"))
+        } else {
+            source_dir
+        };
 
-        if target_dir.exists() && !force && versioned_source.is_none() {
+        if target_dir.exists() && !force && resolved.is_none() {
             if crate::git::is_git_repo(&source_dir) && crate::git::is_git_repo(&target_dir) {
                 let source_hash = crate::git::head_hash(&source_dir).unwrap_or_default();
                 let target_hash = crate::git::head_hash(&target_dir).unwrap_or_default();
@@ -92,9 +120,9 @@ pub fn run(data_dir: Option<&PathBuf>, name: Option<String>, force: bool) -> Res
                 }
             }
 
-            let _ = std::fs::remove_dir_all(&target_dir);
+            remove_target(&target_dir);
         } else if target_dir.exists() {
-            let _ = std::fs::remove_dir_all(&target_dir);
+            remove_target(&target_dir);
         }
 
         match copy_reference(&source_dir, &target_dir, entry) {
@@ -104,7 +132,13 @@ pub fn run(data_dir: Option<&PathBuf>, name: Option<String>, force: bool) -> Res
                     suffix_parts.push(format!("{count} files, filtered"));
                 }
                 if let Some(version) = &entry.version {
-                    suffix_parts.push(format!("version: {version}"));
+                    match resolved.as_ref().and_then(|r| r.version.as_ref()) {
+                        // Show what a range like ^1.2 picked
+                        Some(v) if crate::store::release::is_range(version) => {
+                            suffix_parts.push(format!("version: {version} -> {v}"))
+                        }
+                        _ => suffix_parts.push(format!("version: {version}")),
+                    }
                 }
                 let suffix = if suffix_parts.is_empty() {
                     String::new()
@@ -112,24 +146,41 @@ pub fn run(data_dir: Option<&PathBuf>, name: Option<String>, force: bool) -> Res
                     format!(" ({})", suffix_parts.join(", "))
                 };
                 println!("  {ref_name}: synced{suffix}");
+                match &resolved {
+                    Some(r) => {
+                        let version = SyncedVersion::new(&r.commit, entry);
+                        state.references.insert(ref_name.to_string(), version);
+                    }
+                    None => {
+                        state.references.remove(*ref_name);
+                    }
+                }
                 synced += 1;
             }
             Err(e) => {
                 eprintln!("  {ref_name}: FAILED - {e}");
+                state.references.remove(*ref_name);
                 failed += 1;
             }
         }
-
-        // Clean up versioned temp dir if used
-        if let Some(versioned) = versioned_source {
-            let _ = std::fs::remove_dir_all(&versioned);
-        }
     }
 
+    project
+        .save_sync_state(&state)
+        .context("failed to save sync state")?;
+
     println!("\nSync complete: {synced} synced, {failed} failed");
     Ok(())
 }
 
+fn remove_target(target: &Path) {
+    if target.is_dir() {
+        let _ = std::fs::remove_dir_all(target);
+    } else {
+        let _ = std::fs::remove_file(target);
+    }
+}
+
 fn copy_reference(source: &Path, target: &Path, entry: &ManifestEntry) -> Result<usize> {
     if source.is_file() {
         std::fs::create_dir_all(target.parent().unwrap_or(target))?;
@@ -176,17 +227,17 @@ fn copy_reference(source: &Path, target: &Path, entry: &ManifestEntry) -> Result
         }
 
         // Apply include filter (if any includes, file must match at least one)
-        if let Some(ref inc) = include_set {
-            if !inc.is_match(relative_str.as_ref()) {
-                continue;
-            }
+        if let Some(ref inc) = include_set
+            && !inc.is_match(relative_str.as_ref())
+        {
+            continue;
         }
 
         // Apply exclude filter
-        if let Some(ref exc) = exclude_set {
-            if exc.is_match(relative_str.as_ref()) {
-                continue;
-            }
+        if let Some(ref exc) = exclude_set
+            && exc.is_match(relative_str.as_ref())
+        {
+            continue;
         }
 
         if let Some(parent) = dest.parent() {
@@ -23,12 +23,29 @@ pub fn run(data_dir: Option<&PathBuf>, name: String) -> Result<()> {
         println!("  {} {} {}", entry.hash, entry.date, entry.message);
     }
     println!();
-    println!("Tip: use --pin <hash> when adding to pin to a specific version.");
 
-    // Show available tags
-    if let Ok(tags) = repo.list_tags() {
-        if !tags.is_empty() {
-            println!("Registry tags: {}", tags.join(", "));
+    let releases = repo
+        .releases(&name)
+        .with_context(|| format!("failed to list releases of '{name}'"))?;
+    if !releases.is_empty() {
+        println!("Releases:");
+        for release in &releases {
+            println!("  {:<10} {} {}", release.version, release.commit, release.date);
+        }
+        println!();
+    }
+
+    println!("Tip: use --pin <version|hash> when adding to pin to a specific version.");
+
+    // Show tags of the registry the reference comes from
+    if let Ok(tags) = repo.registry_tags(&name)
+        && !tags.is_empty()
+    {
+        match repo.resolve(&name).map(|r| r.registry_name) {
+            Some(registry) if registry != "local" => {
+                println!("Registry tags ({registry}): {}", tags.join(", "))
+            }
+            _ => println!("Registry tags: {}", tags.join(", ")),
         }
     }
 
@@ -30,6 +30,23 @@ pub enum RefstoreError {
     #[error("failed to parse manifest: {0}")]
     ManifestParse(#[from] toml::de::Error),
 
+    #[error(
+        "{path} uses {kind} format version {found}, but this refstore supports up to version {supported}; upgrade refstore to read it"
+    )]
+    UnsupportedFormatVersion {
+        path: PathBuf,
+        kind: &'static str,
+        found: u32,
+        supported: u32,
+    },
+
+    #[error("{path} has an invalid {kind} format version: {found}")]
+    InvalidFormatVersion {
+        path: PathBuf,
+        kind: &'static str,
+        found: String,
+    },
+
     #[error("manifest already exists at {0}")]
     ManifestExists(PathBuf),
 
@@ -39,6 +56,9 @@ pub enum RefstoreError {
     #[error("reference '{name}' already exists in repository")]
     ReferenceExists { name: String },
 
+    #[error("reference '{name}' is not a snippet")]
+    NotASnippet { name: String },
+
     #[error("invalid reference name '{name}': {reason}")]
     InvalidName { name: String, reason: String },
 
@@ -48,9 +68,34 @@ pub enum RefstoreError {
     #[error("git is not installed or not in PATH")]
     GitNotFound,
 
+    #[error("cannot fetch '{name}': git sources need the git command, which is not installed or not in PATH")]
+    GitSourceNeedsGit { name: String },
+
     #[error("failed to determine data directory; set XDG_DATA_HOME or --data-dir")]
     DataDirNotFound,
 
+    #[error("failed to extract text from {path}: {reason}")]
+    Extract { path: PathBuf, reason: String },
+
+    #[error("source helper '{helper}' failed: {reason}")]
+    PluginFailed { helper: String, reason: String },
+
+    #[error(
+        "possible secrets in '{name}'; pass --allow-secrets to commit anyway or list them in secrets-allowlist:\n{findings}"
+    )]
+    SecretsFound { name: String, findings: String },
+
+    #[error("'{name}' exceeds the store's size limits; pass --force to store it anyway:\n{summary}")]
+    QuotaExceeded { name: String, summary: String },
+
+    #[error(
+        "nothing left to store in '{name}': all {skipped} file(s) are binary; pass --force to store them anyway"
+    )]
+    NothingToStore { name: String, skipped: usize },
+
+    #[error("invalid secrets allowlist {path}: {reason}")]
+    InvalidAllowlist { path: PathBuf, reason: String },
+
     #[error("sync failed for '{name}': {reason}")]
     SyncFailed { name: String, reason: String },
 
@@ -66,9 +111,27 @@ pub enum RefstoreError {
     #[error("bundle '{bundle}' references unknown reference '{reference}'")]
     BundleInvalidReference { bundle: String, reference: String },
 
+    #[error("nothing to undo: {reason}")]
+    NothingToUndo { reason: String },
+
+    #[error("release {version} of '{name}' already exists")]
+    ReleaseExists { name: String, version: String },
+
+    #[error("version '{version}' of '{name}' not found (not a release, registry tag, or commit)")]
+    VersionNotFound { name: String, version: String },
+
+    #[error("no release of '{name}' matches '{version}'")]
+    NoMatchingVersion { name: String, version: String },
+
+    #[error("invalid version requirement '{version}': {reason}")]
+    InvalidVersionReq { version: String, reason: String },
+
     #[error("registry '{name}' not found")]
     RegistryNotFound { name: String },
 
     #[error("registry '{name}' already exists")]
     RegistryExists { name: String },
+
+    #[error("invalid registry order: {reason}")]
+    InvalidRegistryOrder { reason: String },
 }
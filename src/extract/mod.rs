@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::error::RefstoreError;
+use crate::model::ExtractFormat;
+
+/// Walk `content_dir` and write a Markdown sibling for every file matching one
+/// of the requested formats (`spec.pdf` -> `spec.pdf.md`).
+/// Returns the number of files extracted.
+pub fn extract_dir(content_dir: &Path, formats: &[ExtractFormat]) -> Result<usize, RefstoreError> {
+    let mut sources = Vec::new();
+    for entry in walkdir::WalkDir::new(content_dir).min_depth(1) {
+        let entry = entry.map_err(|e| RefstoreError::FileRead {
+            path: content_dir.to_path_buf(),
+            source: e.into(),
+        })?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if let Some(format) = format_for(entry.path())
+            && formats.contains(&format)
+        {
+            sources.push((entry.into_path(), format));
+        }
+    }
+
+    for (path, format) in &sources {
+        let markdown = match format {
+            ExtractFormat::Pdf => pdf_to_markdown(path)?,
+            ExtractFormat::Notebook => notebook_to_markdown(path)?,
+        };
+        let dest = sibling_path(path);
+        fs::write(&dest, markdown).map_err(|source| RefstoreError::FileWrite { path: dest, source })?;
+    }
+
+    Ok(sources.len())
+}
+
+fn format_for(path: &Path) -> Option<ExtractFormat> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "pdf" => Some(ExtractFormat::Pdf),
+        "ipynb" => Some(ExtractFormat::Notebook),
+        _ => None,
+    }
+}
+
+fn sibling_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".md");
+    path.with_file_name(name)
+}
+
+/// Extract the text layer of a PDF, one `<!-- page N -->` marker per page.
+fn pdf_to_markdown(path: &Path) -> Result<String, RefstoreError> {
+    let doc = lopdf::Document::load(path).map_err(|e| RefstoreError::Extract {
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    })?;
+
+    let mut out = String::new();
+    for page in doc.get_pages().keys() {
+        // A page without a usable text layer (scans, broken fonts) shouldn't
+        // fail the whole document; leave its marker with an empty body.
+        let text = doc.extract_text(&[*page]).unwrap_or_default();
+        out.push_str(&format!("<!-- page {page} -->\n\n"));
+        out.push_str(text.trim_end());
+        out.push_str("\n\n");
+    }
+    Ok(out)
+}
+
+/// Render a Jupyter notebook as Markdown: markdown cells verbatim, code cells
+/// fenced with the kernel language, and text outputs fenced as `text`.
+fn notebook_to_markdown(path: &Path) -> Result<String, RefstoreError> {
+    let content = fs::read_to_string(path).map_err(|source| RefstoreError::FileRead {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let notebook: Value = serde_json::from_str(&content).map_err(|e| RefstoreError::Extract {
+        path: path.to_path_buf(),
+        reason: format!("invalid notebook JSON: {e}"),
+    })?;
+
+    let language = notebook
+        .pointer("/metadata/language_info/name")
+        .or_else(|| notebook.pointer("/metadata/kernelspec/language"))
+        .and_then(Value::as_str)
+        .unwrap_or("python");
+
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| RefstoreError::Extract {
+            path: path.to_path_buf(),
+            reason: "notebook has no cells".to_string(),
+        })?;
+
+    let mut out = String::new();
+    for cell in cells {
+        let source = join_text(cell.get("source"));
+        match cell.get("cell_type").and_then(Value::as_str) {
+            Some("markdown") => {
+                out.push_str(source.trim_end());
+                out.push_str("\n\n");
+            }
+            Some("code") => {
+                push_fenced(&mut out, language, &source);
+                for output in cell.get("outputs").and_then(Value::as_array).into_iter().flatten() {
+                    let text = match output.get("output_type").and_then(Value::as_str) {
+                        Some("stream") => join_text(output.get("text")),
+                        Some("execute_result") | Some("display_data") => {
+                            join_text(output.pointer("/data/text~1plain"))
+                        }
+                        _ => String::new(),
+                    };
+                    if !text.trim().is_empty() {
+                        push_fenced(&mut out, "text", &text);
+                    }
+                }
+            }
+            _ => {
+                if !source.trim().is_empty() {
+                    push_fenced(&mut out, "", &source);
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Notebook text fields are either a single string or a list of lines.
+fn join_text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+fn push_fenced(out: &mut String, language: &str, body: &str) {
+    // Use a fence longer than any backtick run in the body so it can't close early.
+    let longest = body
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    out.push_str(&format!("{fence}{language}\n{}\n{fence}\n\n", body.trim_end()));
+}
@@ -0,0 +1,218 @@
+use std::path::Path;
+
+use super::{CheckoutOptions, FileChange, LogEntry, TagEntry};
+use crate::error::RefstoreError;
+use crate::model::GitBackendKind;
+
+/// The git operations refstore runs on its own repositories: the data
+/// directory and registries. Fetching git *sources* through the mirror cache
+/// always uses the `git` command, since it depends on LFS and submodule support.
+pub trait GitBackend: Send + Sync {
+    /// Short name for diagnostics, e.g. in `config show`.
+    fn name(&self) -> &'static str;
+
+    /// Initialize a repo at `path` if one doesn't already exist, with an
+    /// identity configured so commits work in any environment.
+    fn init(&self, path: &Path) -> Result<(), RefstoreError>;
+
+    fn is_repo(&self, path: &Path) -> bool;
+
+    /// Full hash of the commit `HEAD` points at.
+    fn head(&self, repo: &Path) -> Result<String, RefstoreError>;
+
+    /// Record the working-tree state of `paths` (relative to `repo`), including
+    /// deletions, as a new commit. No-op if nothing under them changed.
+    fn commit(&self, repo: &Path, paths: &[&str], message: &str) -> Result<(), RefstoreError>;
+
+    /// What `commit` would record under `path`, with the `path/` prefix
+    /// stripped from the returned paths.
+    fn pending_changes(&self, repo: &Path, path: &str) -> Result<Vec<FileChange>, RefstoreError>;
+
+    /// Put `paths` back to their state at `rev`, deleting files (or whole
+    /// paths) that did not exist at `rev`.
+    fn restore(&self, repo: &Path, rev: &str, paths: &[&str]) -> Result<(), RefstoreError>;
+
+    /// Commits that touched `path`, newest first.
+    fn log_path(&self, repo: &Path, path: &str) -> Result<Vec<LogEntry>, RefstoreError>;
+
+    /// Resolve a revision (tag, branch, `HEAD~1`, abbreviated hash) to a full commit hash.
+    fn rev_parse(&self, repo: &Path, rev: &str) -> Result<String, RefstoreError>;
+
+    fn ref_exists(&self, repo: &Path, rev: &str) -> bool {
+        self.rev_parse(repo, rev).is_ok()
+    }
+
+    /// Whether `path` (a file or directory) exists in the tree of `rev`.
+    fn has_path(&self, repo: &Path, rev: &str, path: &str) -> bool;
+
+    /// Extract `content_path` as of `rev` into `dest`, with the `content_path`
+    /// prefix stripped. Entries that would land outside `dest` are rejected.
+    fn archive(&self, repo: &Path, rev: &str, content_path: &str, dest: &Path) -> Result<(), RefstoreError>;
+
+    /// Tags under `refs/tags/<prefix>`, most recently created first.
+    fn tags(&self, repo: &Path, prefix: &str) -> Result<Vec<TagEntry>, RefstoreError>;
+
+    /// Tag `HEAD`; annotated when there is a message.
+    fn create_tag(&self, repo: &Path, tag: &str, message: Option<&str>) -> Result<(), RefstoreError>;
+
+    /// Clone `url` into `target` at `git_ref` (or its default branch).
+    /// A `depth` of 0 means full history.
+    fn clone(
+        &self,
+        url: &str,
+        target: &Path,
+        git_ref: Option<&str>,
+        depth: u32,
+        options: CheckoutOptions,
+    ) -> Result<(), RefstoreError>;
+
+    fn submodule_add(&self, repo: &Path, url: &str, path: &str) -> Result<(), RefstoreError>;
+
+    fn submodule_remove(&self, repo: &Path, path: &str) -> Result<(), RefstoreError>;
+
+    /// Update submodule(s) to the latest remote commit; all of them if `path` is `None`.
+    fn submodule_update(&self, repo: &Path, path: Option<&str>) -> Result<(), RefstoreError>;
+
+    /// Fetch the submodule at `path` and check out `rev` there, recording it
+    /// as the submodule's `ref` in `.gitmodules`.
+    fn submodule_pin(&self, repo: &Path, path: &str, rev: &str) -> Result<(), RefstoreError>;
+
+    /// The URL `.gitmodules` records for the submodule at `path`, if any.
+    fn submodule_url(&self, repo: &Path, path: &str) -> Result<Option<String>, RefstoreError>;
+
+    /// The revision `submodule_pin` recorded in `.gitmodules` for `path`, if any.
+    fn submodule_ref(&self, repo: &Path, path: &str) -> Result<Option<String>, RefstoreError>;
+
+    /// Tags on commits that build on `rev`, excluding tags of `rev` itself,
+    /// highest version first.
+    fn tags_after(&self, repo: &Path, rev: &str) -> Result<Vec<String>, RefstoreError>;
+}
+
+/// Pick the backend for `kind`. `Auto` uses the `git` command when it is on
+/// `PATH` and falls back to the in-process backend otherwise.
+pub fn select(kind: GitBackendKind) -> Result<Box<dyn GitBackend>, RefstoreError> {
+    match kind {
+        GitBackendKind::Cli => Ok(Box::new(CliBackend)),
+        GitBackendKind::Gix => in_process(),
+        GitBackendKind::Auto if git_on_path() => Ok(Box::new(CliBackend)),
+        GitBackendKind::Auto => in_process().or(Ok(Box::new(CliBackend))),
+    }
+}
+
+#[cfg(feature = "gix")]
+fn in_process() -> Result<Box<dyn GitBackend>, RefstoreError> {
+    Ok(Box::new(super::gitoxide::GixBackend))
+}
+
+#[cfg(not(feature = "gix"))]
+fn in_process() -> Result<Box<dyn GitBackend>, RefstoreError> {
+    Err(RefstoreError::GitCommand(
+        "refstore was built without the in-process git backend (the `gix` feature)".to_string(),
+    ))
+}
+
+/// Look for a `git` executable without running it.
+fn git_on_path() -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    let exe = format!("git{}", std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(&path).any(|dir| dir.join(&exe).is_file())
+}
+
+/// Shells out to the `git` command.
+pub struct CliBackend;
+
+impl GitBackend for CliBackend {
+    fn name(&self) -> &'static str {
+        "git command"
+    }
+
+    fn init(&self, path: &Path) -> Result<(), RefstoreError> {
+        super::init(path)
+    }
+
+    fn is_repo(&self, path: &Path) -> bool {
+        super::is_git_repo(path)
+    }
+
+    fn head(&self, repo: &Path) -> Result<String, RefstoreError> {
+        super::head_hash(repo)
+    }
+
+    fn commit(&self, repo: &Path, paths: &[&str], message: &str) -> Result<(), RefstoreError> {
+        super::commit(repo, paths, message)
+    }
+
+    fn pending_changes(&self, repo: &Path, path: &str) -> Result<Vec<FileChange>, RefstoreError> {
+        super::stage_all(repo, &[path])?;
+        super::staged_changes(repo, path)
+    }
+
+    fn restore(&self, repo: &Path, rev: &str, paths: &[&str]) -> Result<(), RefstoreError> {
+        super::restore_paths(repo, rev, paths)
+    }
+
+    fn log_path(&self, repo: &Path, path: &str) -> Result<Vec<LogEntry>, RefstoreError> {
+        super::log_path(repo, path)
+    }
+
+    fn rev_parse(&self, repo: &Path, rev: &str) -> Result<String, RefstoreError> {
+        super::rev_parse(repo, rev)
+    }
+
+    fn has_path(&self, repo: &Path, rev: &str, path: &str) -> bool {
+        super::has_path(repo, rev, path)
+    }
+
+    fn archive(&self, repo: &Path, rev: &str, content_path: &str, dest: &Path) -> Result<(), RefstoreError> {
+        super::archive_path_at_ref(repo, rev, content_path, dest)
+    }
+
+    fn tags(&self, repo: &Path, prefix: &str) -> Result<Vec<TagEntry>, RefstoreError> {
+        super::tags_with_prefix(repo, prefix)
+    }
+
+    fn create_tag(&self, repo: &Path, tag: &str, message: Option<&str>) -> Result<(), RefstoreError> {
+        super::create_tag(repo, tag, message)
+    }
+
+    fn clone(
+        &self,
+        url: &str,
+        target: &Path,
+        git_ref: Option<&str>,
+        depth: u32,
+        options: CheckoutOptions,
+    ) -> Result<(), RefstoreError> {
+        super::clone_shallow(url, target, git_ref, depth, options)
+    }
+
+    fn submodule_add(&self, repo: &Path, url: &str, path: &str) -> Result<(), RefstoreError> {
+        super::submodule_add(repo, url, path)
+    }
+
+    fn submodule_remove(&self, repo: &Path, path: &str) -> Result<(), RefstoreError> {
+        super::submodule_remove(repo, path)
+    }
+
+    fn submodule_update(&self, repo: &Path, path: Option<&str>) -> Result<(), RefstoreError> {
+        super::submodule_update(repo, path)
+    }
+
+    fn submodule_pin(&self, repo: &Path, path: &str, rev: &str) -> Result<(), RefstoreError> {
+        super::submodule_pin(repo, path, rev)
+    }
+
+    fn submodule_url(&self, repo: &Path, path: &str) -> Result<Option<String>, RefstoreError> {
+        super::submodule_url(repo, path)
+    }
+
+    fn submodule_ref(&self, repo: &Path, path: &str) -> Result<Option<String>, RefstoreError> {
+        super::submodule_ref(repo, path)
+    }
+
+    fn tags_after(&self, repo: &Path, rev: &str) -> Result<Vec<String>, RefstoreError> {
+        super::tags_after(repo, rev)
+    }
+}
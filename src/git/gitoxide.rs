@@ -0,0 +1,622 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use gix::ObjectId;
+use gix::bstr::ByteSlice;
+use gix::objs::tree::EntryKind;
+
+use super::backend::GitBackend;
+use super::{
+    ChangeKind, CheckoutOptions, FileChange, LogEntry, TagEntry, escape_error, link_stays_inside, verify_links,
+};
+use crate::error::RefstoreError;
+
+/// Runs git operations in-process with gitoxide, so the data directory works
+/// without a `git` binary. gitoxide can't clone over every transport or manage
+/// submodules yet, so those operations still use the `git` command.
+pub struct GixBackend;
+
+/// Tree entries keyed by path, relative to whatever they were collected from.
+type Entries = BTreeMap<String, (EntryKind, ObjectId)>;
+
+impl GitBackend for GixBackend {
+    fn name(&self) -> &'static str {
+        "in-process"
+    }
+
+    fn init(&self, path: &Path) -> Result<(), RefstoreError> {
+        if self.is_repo(path) {
+            return Ok(());
+        }
+        gix::init(path).map_err(gix_err)?;
+
+        // Same identity the `git` backend configures
+        let config = path.join(".git").join("config");
+        let mut content = fs::read_to_string(&config).map_err(|source| RefstoreError::FileRead {
+            path: config.clone(),
+            source,
+        })?;
+        content.push_str("[user]\n\tname = refstore\n\temail = refstore@local\n");
+        fs::write(&config, content).map_err(|source| RefstoreError::FileWrite { path: config, source })
+    }
+
+    fn is_repo(&self, path: &Path) -> bool {
+        super::is_git_repo(path)
+    }
+
+    fn head(&self, repo: &Path) -> Result<String, RefstoreError> {
+        let repo = open(repo)?;
+        repo.head_id().map(|id| id.to_string()).map_err(gix_err)
+    }
+
+    fn commit(&self, repo: &Path, paths: &[&str], message: &str) -> Result<(), RefstoreError> {
+        let repo = open(repo)?;
+        let head = repo.head_id().ok().map(|id| id.detach());
+        let base = head_tree(&repo, head)?;
+        let tree = tree_with_worktree(&repo, base, paths)?;
+        if tree == base {
+            return Ok(());
+        }
+
+        let signature = signature(&repo);
+        let mut time = gix::date::parse::TimeBuf::default();
+        let signature = signature.to_ref(&mut time);
+        repo.commit_as(signature, signature, "HEAD", with_newline(message), tree, head)
+            .map_err(gix_err)?;
+
+        // Keep the index in step so the `git` command sees a clean tree
+        let mut index = repo.index_from_tree(&tree).map_err(gix_err)?;
+        index.write(Default::default()).map_err(gix_err)
+    }
+
+    fn pending_changes(&self, repo: &Path, path: &str) -> Result<Vec<FileChange>, RefstoreError> {
+        let repo = open(repo)?;
+        let head = repo.head_id().ok().map(|id| id.detach());
+        let base = head_tree(&repo, head)?;
+        let tree = tree_with_worktree(&repo, base, &[path])?;
+
+        let path = normalize(path);
+        let before = entries_at(&repo, base, path)?;
+        let after = entries_at(&repo, tree, path)?;
+        let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+
+        let mut changes = Vec::new();
+        for name in names {
+            let (kind, old, new) = match (before.get(name), after.get(name)) {
+                (None, Some((_, new))) => (ChangeKind::Added, None, Some(*new)),
+                (Some((_, old)), None) => (ChangeKind::Removed, Some(*old), None),
+                (Some(old), Some(new)) if old != new => (ChangeKind::Modified, Some(old.1), Some(new.1)),
+                _ => continue,
+            };
+            let (lines_added, lines_removed) = line_counts(&repo, old, new)?;
+            changes.push(FileChange {
+                kind,
+                path: name.clone(),
+                lines_added,
+                lines_removed,
+            });
+        }
+        Ok(changes)
+    }
+
+    fn restore(&self, repo: &Path, rev: &str, paths: &[&str]) -> Result<(), RefstoreError> {
+        let repo = open(repo)?;
+        let workdir = workdir(&repo)?.to_path_buf();
+        let tree = commit_tree(&repo, rev)?;
+
+        for path in paths {
+            let path = normalize(path);
+            let target = workdir.join(path);
+            if let Ok(meta) = fs::symlink_metadata(&target) {
+                let removed = if meta.is_dir() {
+                    fs::remove_dir_all(&target)
+                } else {
+                    fs::remove_file(&target)
+                };
+                removed.map_err(|source| RefstoreError::FileWrite {
+                    path: target.clone(),
+                    source,
+                })?;
+            }
+            if let Some(entry) = tree.lookup_entry_by_path(path).map_err(gix_err)? {
+                checkout(&repo, entry.mode().kind(), entry.object_id(), &target, None)
+                    .map_err(|source| RefstoreError::FileWrite { path: target, source })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn log_path(&self, repo: &Path, path: &str) -> Result<Vec<LogEntry>, RefstoreError> {
+        let repo = open(repo)?;
+        let Ok(head) = repo.head_id() else {
+            return Ok(Vec::new());
+        };
+        let path = normalize(path);
+
+        let mut entries = Vec::new();
+        for info in repo.rev_walk([head]).all().map_err(gix_err)? {
+            let info = info.map_err(gix_err)?;
+            let commit = info.object().map_err(gix_err)?;
+            let touched = path.is_empty() || {
+                let here = entry_id(&repo, commit.tree_id().map_err(gix_err)?.detach(), path)?;
+                let before = match info.parent_ids().next() {
+                    Some(parent) => {
+                        let parent = repo.find_commit(parent).map_err(gix_err)?;
+                        entry_id(&repo, parent.tree_id().map_err(gix_err)?.detach(), path)?
+                    }
+                    None => None,
+                };
+                here != before
+            };
+            if !touched {
+                continue;
+            }
+
+            let author = commit.author().map_err(gix_err)?;
+            entries.push(LogEntry {
+                hash: info.id.to_string(),
+                date: author.time().map(|t| format_time(t, "%+")).unwrap_or_default(),
+                message: commit.message().map_err(gix_err)?.summary().to_string(),
+            });
+        }
+        Ok(entries)
+    }
+
+    fn rev_parse(&self, repo: &Path, rev: &str) -> Result<String, RefstoreError> {
+        let repo = open(repo)?;
+        let spec = format!("{rev}^{{commit}}");
+        repo.rev_parse_single(spec.as_str())
+            .map(|id| id.to_string())
+            .map_err(|_| RefstoreError::GitCommand(format!("unknown revision '{rev}'")))
+    }
+
+    fn has_path(&self, repo: &Path, rev: &str, path: &str) -> bool {
+        open(repo)
+            .and_then(|repo| {
+                let tree = commit_tree(&repo, rev)?;
+                Ok(tree.lookup_entry_by_path(normalize(path)).map_err(gix_err)?.is_some())
+            })
+            .unwrap_or(false)
+    }
+
+    fn archive(&self, repo: &Path, rev: &str, content_path: &str, dest: &Path) -> Result<(), RefstoreError> {
+        fs::create_dir_all(dest).map_err(|source| RefstoreError::DirCreate {
+            path: dest.to_path_buf(),
+            source,
+        })?;
+
+        let repo = open(repo)?;
+        let archive_error = |reason: String| {
+            RefstoreError::GitCommand(format!("failed to archive '{content_path}' at ref '{rev}': {reason}"))
+        };
+        let tree = commit_tree(&repo, rev).map_err(|e| archive_error(e.to_string()))?;
+        let entry = tree
+            .lookup_entry_by_path(content_path)
+            .map_err(gix_err)?
+            .filter(|e| e.mode().is_tree())
+            .ok_or_else(|| archive_error("no such directory".to_string()))?;
+
+        checkout(&repo, EntryKind::Tree, entry.object_id(), dest, Some(Path::new("")))
+            .and_then(|()| verify_links(dest))
+            .map_err(|e| RefstoreError::GitCommand(format!("failed to extract content at ref '{rev}': {e}")))
+    }
+
+    fn tags(&self, repo: &Path, prefix: &str) -> Result<Vec<TagEntry>, RefstoreError> {
+        let repo = open(repo)?;
+        let references = repo.references().map_err(gix_err)?;
+
+        let mut tags = Vec::new();
+        for reference in references.tags().map_err(gix_err)? {
+            let mut reference = reference.map_err(gix_err)?;
+            let name = reference.name().shorten().to_string();
+            if !name.starts_with(prefix) {
+                continue;
+            }
+
+            let object = reference.id().object().map_err(gix_err)?;
+            let time = match object.kind {
+                gix::objs::Kind::Tag => object.into_tag().tagger().map_err(gix_err)?.and_then(|t| t.time().ok()),
+                _ => None,
+            };
+            let commit = reference.peel_to_commit().map_err(gix_err)?;
+            let time = match time {
+                Some(time) => time,
+                None => commit.time().map_err(gix_err)?,
+            };
+            tags.push((time.seconds, TagEntry {
+                name,
+                commit: commit.id().to_hex_with_len(7).to_string(),
+                date: format_time(time, "%Y-%m-%d"),
+            }));
+        }
+
+        tags.sort_by_key(|(seconds, _)| std::cmp::Reverse(*seconds));
+        Ok(tags.into_iter().map(|(_, tag)| tag).collect())
+    }
+
+    fn create_tag(&self, repo: &Path, tag: &str, message: Option<&str>) -> Result<(), RefstoreError> {
+        use gix::refs::transaction::PreviousValue;
+
+        let repo = open(repo)?;
+        let head = repo.head_id().map_err(gix_err)?;
+        let created = match message {
+            Some(message) => {
+                let signature = signature(&repo);
+                let mut time = gix::date::parse::TimeBuf::default();
+                repo.tag(
+                    tag,
+                    head,
+                    gix::objs::Kind::Commit,
+                    Some(signature.to_ref(&mut time)),
+                    with_newline(message),
+                    PreviousValue::MustNotExist,
+                )
+                .map(drop)
+                .map_err(gix_err)
+            }
+            None => repo
+                .tag_reference(tag, head, PreviousValue::MustNotExist)
+                .map(drop)
+                .map_err(gix_err),
+        };
+        created.map_err(|e| RefstoreError::GitCommand(format!("failed to create tag '{tag}': {e}")))
+    }
+
+    fn clone(
+        &self,
+        url: &str,
+        target: &Path,
+        git_ref: Option<&str>,
+        depth: u32,
+        options: CheckoutOptions,
+    ) -> Result<(), RefstoreError> {
+        super::clone_shallow(url, target, git_ref, depth, options)
+    }
+
+    fn submodule_add(&self, repo: &Path, url: &str, path: &str) -> Result<(), RefstoreError> {
+        super::submodule_add(repo, url, path)
+    }
+
+    fn submodule_remove(&self, repo: &Path, path: &str) -> Result<(), RefstoreError> {
+        super::submodule_remove(repo, path)
+    }
+
+    fn submodule_update(&self, repo: &Path, path: Option<&str>) -> Result<(), RefstoreError> {
+        super::submodule_update(repo, path)
+    }
+
+    fn submodule_pin(&self, repo: &Path, path: &str, rev: &str) -> Result<(), RefstoreError> {
+        super::submodule_pin(repo, path, rev)
+    }
+
+    fn submodule_url(&self, repo: &Path, path: &str) -> Result<Option<String>, RefstoreError> {
+        gitmodules_value(repo, path, "url")
+    }
+
+    fn submodule_ref(&self, repo: &Path, path: &str) -> Result<Option<String>, RefstoreError> {
+        gitmodules_value(repo, path, "ref")
+    }
+
+    fn tags_after(&self, repo: &Path, rev: &str) -> Result<Vec<String>, RefstoreError> {
+        super::tags_after(repo, rev)
+    }
+}
+
+/// `submodule.<path>.<key>` from `.gitmodules`; `None` if it is unset or empty.
+fn gitmodules_value(repo: &Path, path: &str, key: &str) -> Result<Option<String>, RefstoreError> {
+    let file = repo.join(".gitmodules");
+    if !file.exists() {
+        return Ok(None);
+    }
+    let config = gix::config::File::from_path_no_includes(file, gix::config::Source::Local).map_err(gix_err)?;
+    Ok(config
+        .string_by("submodule", Some(path.into()), key)
+        .map(|value| value.to_string())
+        .filter(|value| !value.is_empty()))
+}
+
+fn gix_err(e: impl std::fmt::Display) -> RefstoreError {
+    RefstoreError::GitCommand(e.to_string())
+}
+
+fn open(path: &Path) -> Result<gix::Repository, RefstoreError> {
+    gix::open(path).map_err(gix_err)
+}
+
+fn workdir(repo: &gix::Repository) -> Result<&Path, RefstoreError> {
+    repo.workdir()
+        .ok_or_else(|| RefstoreError::GitCommand("repository has no working tree".to_string()))
+}
+
+/// `.`, `./content/` and `content` all name the same thing to git; the root is "".
+fn normalize(path: &str) -> &str {
+    let path = path.trim_start_matches("./").trim_end_matches('/');
+    if path == "." { "" } else { path }
+}
+
+/// Git's `cleanup` mode ends every message with a newline.
+fn with_newline(message: &str) -> String {
+    format!("{}\n", message.trim_end_matches('\n'))
+}
+
+/// The configured identity (set by `init`), or refstore's own.
+fn signature(repo: &gix::Repository) -> gix::actor::Signature {
+    let (name, email) = match repo.committer() {
+        Some(Ok(sig)) => (sig.name.to_owned(), sig.email.to_owned()),
+        _ => ("refstore".into(), "refstore@local".into()),
+    };
+    gix::actor::Signature {
+        name,
+        email,
+        time: gix::date::Time::now_local_or_utc(),
+    }
+}
+
+fn format_time(time: gix::date::Time, format: &str) -> String {
+    let offset = chrono::FixedOffset::east_opt(time.offset).unwrap_or(chrono::FixedOffset::east_opt(0).unwrap());
+    chrono::DateTime::from_timestamp(time.seconds, 0)
+        .map(|t| t.with_timezone(&offset).format(format).to_string())
+        .unwrap_or_default()
+}
+
+fn head_tree(repo: &gix::Repository, head: Option<ObjectId>) -> Result<ObjectId, RefstoreError> {
+    match head {
+        Some(id) => {
+            let commit = repo.find_commit(id).map_err(gix_err)?;
+            Ok(commit.tree_id().map_err(gix_err)?.detach())
+        }
+        None => Ok(ObjectId::empty_tree(repo.object_hash())),
+    }
+}
+
+fn commit_tree<'repo>(repo: &'repo gix::Repository, rev: &str) -> Result<gix::Tree<'repo>, RefstoreError> {
+    let spec = format!("{rev}^{{commit}}");
+    let id = repo
+        .rev_parse_single(spec.as_str())
+        .map_err(|_| RefstoreError::GitCommand(format!("unknown revision '{rev}'")))?;
+    let commit = repo.find_commit(id).map_err(gix_err)?;
+    commit.tree().map_err(gix_err)
+}
+
+/// The id of whatever is at `path` in `tree`, if anything.
+fn entry_id(repo: &gix::Repository, tree: ObjectId, path: &str) -> Result<Option<ObjectId>, RefstoreError> {
+    let tree = repo.find_tree(tree).map_err(gix_err)?;
+    Ok(tree.lookup_entry_by_path(path).map_err(gix_err)?.map(|e| e.object_id()))
+}
+
+/// `base` with everything under `paths` replaced by what's in the working tree.
+fn tree_with_worktree(repo: &gix::Repository, base: ObjectId, paths: &[&str]) -> Result<ObjectId, RefstoreError> {
+    let paths: Vec<&str> = paths.iter().map(|p| normalize(p)).collect();
+    let (base, paths) = if paths.contains(&"") {
+        (ObjectId::empty_tree(repo.object_hash()), vec![""])
+    } else {
+        (base, paths)
+    };
+
+    let mut editor = repo.edit_tree(base).map_err(gix_err)?;
+    for path in paths {
+        if !path.is_empty() {
+            editor.remove(path).map_err(gix_err)?;
+        }
+        for (name, (kind, id)) in worktree_entries(repo, path)? {
+            editor.upsert(name, kind, id).map_err(gix_err)?;
+        }
+    }
+    Ok(editor.write().map_err(gix_err)?.detach())
+}
+
+/// Write the files under `path` in the working tree to the object database.
+/// Ignored files are skipped and nested repositories become gitlinks, as
+/// with `git add`. Keys are relative to the repository root.
+fn worktree_entries(repo: &gix::Repository, path: &str) -> Result<Entries, RefstoreError> {
+    use gix::index::entry::Mode;
+
+    let workdir = workdir(repo)?;
+    let start = workdir.join(path);
+    let mut entries = Entries::new();
+    if fs::symlink_metadata(&start).is_err() {
+        return Ok(entries);
+    }
+
+    let index = repo.index_or_empty().map_err(gix_err)?;
+    let mut excludes = repo
+        .excludes(
+            &index,
+            None,
+            gix::worktree::stack::state::ignore::Source::WorktreeThenIdMappingIfNotSkipped,
+        )
+        .map_err(gix_err)?;
+
+    let mut walk = walkdir::WalkDir::new(&start).sort_by_file_name().into_iter();
+    while let Some(entry) = walk.next() {
+        let entry = entry.map_err(gix_err)?;
+        let relative = entry.path().strip_prefix(workdir).unwrap_or(entry.path());
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let is_dir = entry.file_type().is_dir();
+        let mode = if is_dir { Mode::DIR } else { Mode::FILE };
+        if entry.file_name() == ".git" || excludes.at_path(relative, Some(mode)).map_err(gix_err)?.is_excluded() {
+            if is_dir {
+                walk.skip_current_dir();
+            }
+            continue;
+        }
+        if is_dir {
+            if entry.path().join(".git").exists() {
+                if let Some(head) = gix::open(entry.path()).ok().and_then(|r| r.head_id().ok().map(|id| id.detach())) {
+                    entries.insert(to_slash(relative), (EntryKind::Commit, head));
+                }
+                walk.skip_current_dir();
+            }
+            continue;
+        }
+
+        let (kind, data) = if entry.file_type().is_symlink() {
+            let target = fs::read_link(entry.path()).map_err(|source| RefstoreError::FileRead {
+                path: entry.path().to_path_buf(),
+                source,
+            })?;
+            (EntryKind::Link, gix::path::into_bstr(target).into_owned().into())
+        } else {
+            let data = fs::read(entry.path()).map_err(|source| RefstoreError::FileRead {
+                path: entry.path().to_path_buf(),
+                source,
+            })?;
+            (file_kind(&entry), data)
+        };
+        let id = repo.write_blob(&data).map_err(gix_err)?.detach();
+        entries.insert(to_slash(relative), (kind, id));
+    }
+    Ok(entries)
+}
+
+#[cfg(unix)]
+fn file_kind(entry: &walkdir::DirEntry) -> EntryKind {
+    use std::os::unix::fs::PermissionsExt;
+    match entry.metadata() {
+        Ok(meta) if meta.permissions().mode() & 0o111 != 0 => EntryKind::BlobExecutable,
+        _ => EntryKind::Blob,
+    }
+}
+
+#[cfg(not(unix))]
+fn file_kind(_entry: &walkdir::DirEntry) -> EntryKind {
+    EntryKind::Blob
+}
+
+fn to_slash(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Every non-tree entry at or below `path` in `tree`, keyed relative to `path`.
+fn entries_at(repo: &gix::Repository, tree: ObjectId, path: &str) -> Result<Entries, RefstoreError> {
+    let root = repo.find_tree(tree).map_err(gix_err)?;
+    let mut entries = Entries::new();
+    let found = if path.is_empty() {
+        Some((EntryKind::Tree, tree))
+    } else {
+        root.lookup_entry_by_path(path)
+            .map_err(gix_err)?
+            .map(|e| (e.mode().kind(), e.object_id()))
+    };
+    match found {
+        Some((EntryKind::Tree, id)) => collect_entries(repo, id, "", &mut entries)?,
+        Some(entry) => {
+            entries.insert(path.to_string(), entry);
+        }
+        None => {}
+    }
+    Ok(entries)
+}
+
+fn collect_entries(repo: &gix::Repository, tree: ObjectId, prefix: &str, out: &mut Entries) -> Result<(), RefstoreError> {
+    let tree = repo.find_tree(tree).map_err(gix_err)?;
+    for entry in tree.iter() {
+        let entry = entry.map_err(gix_err)?;
+        let name = format!("{prefix}{}", entry.filename());
+        match entry.mode().kind() {
+            EntryKind::Tree => collect_entries(repo, entry.object_id(), &format!("{name}/"), out)?,
+            kind => {
+                out.insert(name, (kind, entry.object_id()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Lines added and removed between two blobs, like `git diff --numstat`.
+/// `None` for binary content.
+fn line_counts(
+    repo: &gix::Repository,
+    old: Option<ObjectId>,
+    new: Option<ObjectId>,
+) -> Result<(Option<usize>, Option<usize>), RefstoreError> {
+    use gix::diff::blob::{Algorithm, diff, intern::InternedInput, sink::Counter};
+
+    let read = |id: Option<ObjectId>| -> Result<Vec<u8>, RefstoreError> {
+        match id {
+            Some(id) => match repo.find_object(id).map_err(gix_err)? {
+                object if object.kind == gix::objs::Kind::Blob => Ok(object.detach().data),
+                // Submodule commits have no lines
+                _ => Ok(Vec::new()),
+            },
+            None => Ok(Vec::new()),
+        }
+    };
+    let (before, after) = (read(old)?, read(new)?);
+    if before.contains(&0) || after.contains(&0) {
+        return Ok((None, None));
+    }
+
+    let input = InternedInput::new(before.as_bytes(), after.as_bytes());
+    let counts = diff(Algorithm::Myers, &input, Counter::default());
+    Ok((Some(counts.insertions as usize), Some(counts.removals as usize)))
+}
+
+/// Write the object `id` of kind `kind` to `target`. With `archive_path` set
+/// (the entry's path relative to the extraction root), symlinks that would
+/// point outside the root are refused.
+fn checkout(
+    repo: &gix::Repository,
+    kind: EntryKind,
+    id: ObjectId,
+    target: &Path,
+    archive_path: Option<&Path>,
+) -> std::io::Result<()> {
+    let find = |id: ObjectId| repo.find_object(id).map_err(std::io::Error::other);
+    match kind {
+        EntryKind::Tree => {
+            fs::create_dir_all(target)?;
+            let tree = find(id)?.into_tree();
+            for entry in tree.iter() {
+                let entry = entry.map_err(std::io::Error::other)?;
+                let name = gix::path::try_from_bstr(entry.filename()).map_err(std::io::Error::other)?;
+                let child = archive_path.map(|p| p.join(name.as_ref()));
+                checkout(
+                    repo,
+                    entry.mode().kind(),
+                    entry.object_id(),
+                    &target.join(name.as_ref()),
+                    child.as_deref(),
+                )?;
+            }
+        }
+        EntryKind::Blob | EntryKind::BlobExecutable => {
+            fs::write(target, &find(id)?.data)?;
+            #[cfg(unix)]
+            if kind == EntryKind::BlobExecutable {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(target, fs::Permissions::from_mode(0o755))?;
+            }
+        }
+        EntryKind::Link => {
+            let link = gix::path::try_from_bstring(gix::bstr::BString::from(find(id)?.detach().data))
+                .map_err(std::io::Error::other)?;
+            if let Some(relative) = archive_path
+                && !link_stays_inside(relative, &link)
+            {
+                return Err(escape_error(relative));
+            }
+            symlink(&link, target)?;
+        }
+        // `git archive` leaves an empty directory for a submodule
+        EntryKind::Commit => fs::create_dir_all(target)?,
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(link: &Path, target: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(link, target)
+}
+
+#[cfg(not(unix))]
+fn symlink(link: &Path, target: &Path) -> std::io::Result<()> {
+    // Without symlink support, keep the link text like git does with `core.symlinks=false`
+    fs::write(target, gix::path::into_bstr(link).as_ref())
+}
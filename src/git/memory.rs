@@ -0,0 +1,379 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use sha2::{Digest, Sha256};
+
+use super::backend::GitBackend;
+use super::{ChangeKind, CheckoutOptions, FileChange, LogEntry, TagEntry};
+use crate::error::RefstoreError;
+
+/// A `GitBackend` that keeps history in memory, so store logic can be unit
+/// tested without spawning `git`. Each commit is a snapshot of the files under
+/// the committed paths; cloning and submodules are not supported. Clones
+/// share history, so a test can reopen a store with the same backend.
+#[derive(Clone, Default)]
+pub struct MemoryBackend {
+    repos: Arc<Mutex<HashMap<PathBuf, Repo>>>,
+}
+
+#[derive(Default)]
+struct Repo {
+    commits: Vec<Commit>,
+    /// Tag name and the index of the commit it points at, oldest first.
+    tags: Vec<(String, usize)>,
+}
+
+struct Commit {
+    hash: String,
+    date: String,
+    message: String,
+    files: Files,
+}
+
+type Files = BTreeMap<String, Vec<u8>>;
+
+impl MemoryBackend {
+    fn with_repo<T>(&self, repo: &Path, f: impl FnOnce(&mut Repo) -> Result<T, RefstoreError>) -> Result<T, RefstoreError> {
+        let mut repos = self.repos.lock().unwrap_or_else(|e| e.into_inner());
+        let repo = repos
+            .get_mut(repo)
+            .ok_or_else(|| RefstoreError::GitCommand(format!("not a repository: {}", repo.display())))?;
+        f(repo)
+    }
+}
+
+impl Repo {
+    fn head(&self) -> Option<&Commit> {
+        self.commits.last()
+    }
+
+    fn head_files(&self) -> Files {
+        self.head().map(|c| c.files.clone()).unwrap_or_default()
+    }
+
+    /// `HEAD`, `HEAD~N`, a tag, or a (prefix of a) commit hash.
+    fn resolve(&self, rev: &str) -> Result<usize, RefstoreError> {
+        let unknown = || RefstoreError::GitCommand(format!("unknown revision '{rev}'"));
+        let last = self.commits.len().checked_sub(1).ok_or_else(unknown)?;
+        if rev == "HEAD" {
+            return Ok(last);
+        }
+        if let Some((base, back)) = rev.rsplit_once('~') {
+            let back: usize = back.parse().map_err(|_| unknown())?;
+            return self.resolve(base)?.checked_sub(back).ok_or_else(unknown);
+        }
+        if let Some((_, index)) = self.tags.iter().find(|(name, _)| name == rev) {
+            return Ok(*index);
+        }
+        self.commits
+            .iter()
+            .position(|c| rev.len() >= 4 && c.hash.starts_with(rev))
+            .ok_or_else(unknown)
+    }
+}
+
+impl GitBackend for MemoryBackend {
+    fn name(&self) -> &'static str {
+        "in-memory"
+    }
+
+    fn init(&self, path: &Path) -> Result<(), RefstoreError> {
+        let mut repos = self.repos.lock().unwrap_or_else(|e| e.into_inner());
+        repos.entry(path.to_path_buf()).or_default();
+        Ok(())
+    }
+
+    fn is_repo(&self, path: &Path) -> bool {
+        let repos = self.repos.lock().unwrap_or_else(|e| e.into_inner());
+        repos.contains_key(path)
+    }
+
+    fn head(&self, repo: &Path) -> Result<String, RefstoreError> {
+        self.with_repo(repo, |r| {
+            r.head()
+                .map(|c| c.hash.clone())
+                .ok_or_else(|| RefstoreError::GitCommand("no commits yet".to_string()))
+        })
+    }
+
+    fn commit(&self, repo: &Path, paths: &[&str], message: &str) -> Result<(), RefstoreError> {
+        let files = snapshot(repo, paths, self.with_repo(repo, |r| Ok(r.head_files()))?)?;
+        self.with_repo(repo, |r| {
+            if r.head().is_some_and(|c| c.files == files) || (r.head().is_none() && files.is_empty()) {
+                return Ok(());
+            }
+            let mut hasher = Sha256::new();
+            hasher.update(r.head().map(|c| c.hash.as_str()).unwrap_or_default());
+            hasher.update(message);
+            for (path, data) in &files {
+                hasher.update(path);
+                hasher.update(data);
+            }
+            let hash: String = hasher.finalize()[..20].iter().map(|b| format!("{b:02x}")).collect();
+            r.commits.push(Commit {
+                hash,
+                date: chrono::Utc::now().to_rfc3339(),
+                message: message.to_string(),
+                files,
+            });
+            Ok(())
+        })
+    }
+
+    fn pending_changes(&self, repo: &Path, path: &str) -> Result<Vec<FileChange>, RefstoreError> {
+        let before = self.with_repo(repo, |r| Ok(r.head_files()))?;
+        let after = snapshot(repo, &[path], before.clone())?;
+        let prefix = format!("{}/", normalize(path));
+        let under = |files: &Files| -> Files {
+            files
+                .iter()
+                .filter_map(|(p, data)| Some((p.strip_prefix(&prefix)?.to_string(), data.clone())))
+                .collect()
+        };
+        let (before, after) = (under(&before), under(&after));
+
+        let mut changes = Vec::new();
+        for (path, old) in &before {
+            match after.get(path) {
+                None => changes.push(change(ChangeKind::Removed, path, old, &[])),
+                Some(new) if new != old => changes.push(change(ChangeKind::Modified, path, old, new)),
+                Some(_) => {}
+            }
+        }
+        for (path, new) in &after {
+            if !before.contains_key(path) {
+                changes.push(change(ChangeKind::Added, path, &[], new));
+            }
+        }
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(changes)
+    }
+
+    fn restore(&self, repo: &Path, rev: &str, paths: &[&str]) -> Result<(), RefstoreError> {
+        let files = self.with_repo(repo, |r| {
+            let index = r.resolve(rev)?;
+            Ok(r.commits[index].files.clone())
+        })?;
+        for path in paths {
+            let path = normalize(path);
+            let target = repo.join(path);
+            if target.is_dir() {
+                let _ = fs::remove_dir_all(&target);
+            } else {
+                let _ = fs::remove_file(&target);
+            }
+            let prefix = format!("{path}/");
+            for (file, data) in &files {
+                if file == path || file.starts_with(&prefix) {
+                    write_file(&repo.join(file), data)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn log_path(&self, repo: &Path, path: &str) -> Result<Vec<LogEntry>, RefstoreError> {
+        let path = normalize(path);
+        self.with_repo(repo, |r| {
+            let mut previous = Files::new();
+            let mut entries = Vec::new();
+            for commit in &r.commits {
+                let touched = under_path(&commit.files, path) != under_path(&previous, path);
+                if touched {
+                    entries.push(LogEntry {
+                        hash: commit.hash.clone(),
+                        date: commit.date.clone(),
+                        message: commit.message.clone(),
+                    });
+                }
+                previous = commit.files.clone();
+            }
+            entries.reverse();
+            Ok(entries)
+        })
+    }
+
+    fn rev_parse(&self, repo: &Path, rev: &str) -> Result<String, RefstoreError> {
+        self.with_repo(repo, |r| Ok(r.commits[r.resolve(rev)?].hash.clone()))
+    }
+
+    fn has_path(&self, repo: &Path, rev: &str, path: &str) -> bool {
+        self.with_repo(repo, |r| Ok(!under_path(&r.commits[r.resolve(rev)?].files, normalize(path)).is_empty()))
+            .unwrap_or(false)
+    }
+
+    fn archive(&self, repo: &Path, rev: &str, content_path: &str, dest: &Path) -> Result<(), RefstoreError> {
+        let files = self.with_repo(repo, |r| Ok(r.commits[r.resolve(rev)?].files.clone()))?;
+        let prefix = format!("{}/", normalize(content_path));
+        let mut found = false;
+        for (file, data) in &files {
+            if let Some(relative) = file.strip_prefix(&prefix) {
+                write_file(&dest.join(relative), data)?;
+                found = true;
+            }
+        }
+        if !found {
+            return Err(RefstoreError::GitCommand(format!(
+                "failed to archive '{content_path}' at ref '{rev}': no such directory"
+            )));
+        }
+        Ok(())
+    }
+
+    fn tags(&self, repo: &Path, prefix: &str) -> Result<Vec<TagEntry>, RefstoreError> {
+        self.with_repo(repo, |r| {
+            Ok(r.tags
+                .iter()
+                .rev()
+                .filter(|(name, _)| name.starts_with(prefix))
+                .map(|(name, index)| TagEntry {
+                    name: name.clone(),
+                    commit: r.commits[*index].hash[..7].to_string(),
+                    date: r.commits[*index].date[..10].to_string(),
+                })
+                .collect())
+        })
+    }
+
+    fn create_tag(&self, repo: &Path, tag: &str, _message: Option<&str>) -> Result<(), RefstoreError> {
+        self.with_repo(repo, |r| {
+            if r.tags.iter().any(|(name, _)| name == tag) {
+                return Err(RefstoreError::GitCommand(format!("tag '{tag}' already exists")));
+            }
+            let head = r.resolve("HEAD")?;
+            r.tags.push((tag.to_string(), head));
+            Ok(())
+        })
+    }
+
+    fn clone(
+        &self,
+        url: &str,
+        _target: &Path,
+        _git_ref: Option<&str>,
+        _depth: u32,
+        _options: CheckoutOptions,
+    ) -> Result<(), RefstoreError> {
+        Err(unsupported(&format!("clone {url}")))
+    }
+
+    fn submodule_add(&self, _repo: &Path, url: &str, _path: &str) -> Result<(), RefstoreError> {
+        Err(unsupported(&format!("add submodule {url}")))
+    }
+
+    fn submodule_remove(&self, _repo: &Path, path: &str) -> Result<(), RefstoreError> {
+        Err(unsupported(&format!("remove submodule {path}")))
+    }
+
+    fn submodule_update(&self, _repo: &Path, _path: Option<&str>) -> Result<(), RefstoreError> {
+        Err(unsupported("update submodules"))
+    }
+
+    fn submodule_pin(&self, _repo: &Path, path: &str, _rev: &str) -> Result<(), RefstoreError> {
+        Err(unsupported(&format!("pin submodule {path}")))
+    }
+
+    // Submodules can't be added here, so none are ever recorded
+    fn submodule_url(&self, _repo: &Path, _path: &str) -> Result<Option<String>, RefstoreError> {
+        Ok(None)
+    }
+
+    fn submodule_ref(&self, _repo: &Path, _path: &str) -> Result<Option<String>, RefstoreError> {
+        Ok(None)
+    }
+
+    fn tags_after(&self, _repo: &Path, rev: &str) -> Result<Vec<String>, RefstoreError> {
+        Err(unsupported(&format!("list tags after {rev}")))
+    }
+}
+
+fn unsupported(what: &str) -> RefstoreError {
+    RefstoreError::GitCommand(format!("the in-memory backend can't {what}"))
+}
+
+fn normalize(path: &str) -> &str {
+    let path = path.trim_start_matches("./").trim_end_matches('/');
+    if path == "." { "" } else { path }
+}
+
+fn under_path(files: &Files, path: &str) -> Files {
+    let prefix = format!("{path}/");
+    files
+        .iter()
+        .filter(|(p, _)| path.is_empty() || *p == path || p.starts_with(&prefix))
+        .map(|(p, data)| (p.clone(), data.clone()))
+        .collect()
+}
+
+/// `base` with everything under `paths` replaced by the files on disk, minus
+/// `.git` and whatever the repo's top-level `.gitignore` names.
+fn snapshot(repo: &Path, paths: &[&str], mut files: Files) -> Result<Files, RefstoreError> {
+    let ignored: Vec<String> = fs::read_to_string(repo.join(".gitignore"))
+        .unwrap_or_default()
+        .lines()
+        .map(|l| l.trim().trim_end_matches('/').to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    for path in paths {
+        let path = normalize(path);
+        let prefix = format!("{path}/");
+        files.retain(|p, _| !(path.is_empty() || p == path || p.starts_with(&prefix)));
+
+        let walk = walkdir::WalkDir::new(repo.join(path))
+            .into_iter()
+            .filter_entry(|e| e.file_name() != ".git")
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_file());
+        for entry in walk {
+            let relative = entry.path().strip_prefix(repo).unwrap_or(entry.path());
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            let top = relative.split('/').next().unwrap_or_default();
+            if ignored.iter().any(|i| i == top) {
+                continue;
+            }
+            let data = fs::read(entry.path()).map_err(|source| RefstoreError::FileRead {
+                path: entry.path().to_path_buf(),
+                source,
+            })?;
+            files.insert(relative, data);
+        }
+    }
+    Ok(files)
+}
+
+fn write_file(path: &Path, data: &[u8]) -> Result<(), RefstoreError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|source| RefstoreError::DirCreate {
+            path: parent.to_path_buf(),
+            source,
+        })?;
+    }
+    fs::write(path, data).map_err(|source| RefstoreError::FileWrite {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// A change with line counts from comparing the two sides line by line
+/// (as multisets, which is close enough for a test double).
+fn change(kind: ChangeKind, path: &str, old: &[u8], new: &[u8]) -> FileChange {
+    let count = |a: &[u8], b: &[u8]| {
+        let mut lines: HashMap<&[u8], isize> = HashMap::new();
+        for line in a.split(|&c| c == b'\n').filter(|l| !l.is_empty()) {
+            *lines.entry(line).or_default() += 1;
+        }
+        for line in b.split(|&c| c == b'\n').filter(|l| !l.is_empty()) {
+            *lines.entry(line).or_default() -= 1;
+        }
+        lines.values().filter(|n| **n > 0).sum::<isize>() as usize
+    };
+    FileChange {
+        kind,
+        path: path.to_string(),
+        lines_added: Some(count(new, old)),
+        lines_removed: Some(count(old, new)),
+    }
+}
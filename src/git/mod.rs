@@ -1,8 +1,20 @@
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
-use std::process::Command;
+use std::io::{self, Read};
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
 
 use crate::error::RefstoreError;
+use crate::model::{GitRefKind, LfsMode};
+
+mod backend;
+#[cfg(feature = "gix")]
+mod gitoxide;
+#[cfg(test)]
+pub mod memory;
+
+pub use backend::{GitBackend, select};
 
 pub fn ensure_git() -> Result<(), RefstoreError> {
     Command::new("git")
@@ -39,51 +51,36 @@ pub fn init(path: &Path) -> Result<(), RefstoreError> {
     Ok(())
 }
 
-/// Stage specific paths and create a commit.
+/// Stage specific paths, including deletions, and create a commit.
 /// `paths` are relative to `repo_path`.
 pub fn commit(repo_path: &Path, paths: &[&str], message: &str) -> Result<(), RefstoreError> {
-    // Stage the specified paths
-    for path in paths {
-        let mut cmd = Command::new("git");
-        cmd.args(["add", path]).current_dir(repo_path);
-        let output = cmd.output().map_err(|_| RefstoreError::GitNotFound)?;
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(RefstoreError::GitCommand(stderr.to_string()));
-        }
-    }
-
-    // Check if there's anything to commit (avoid empty commits)
-    let status_output = Command::new("git")
-        .args(["diff", "--cached", "--quiet"])
-        .current_dir(repo_path)
-        .status()
-        .map_err(|_| RefstoreError::GitNotFound)?;
-
-    // Exit code 0 means no staged changes - nothing to commit
-    if status_output.success() {
-        return Ok(());
-    }
-
-    // Commit
-    run_git(repo_path, &["commit", "-m", message])?;
-
-    Ok(())
+    stage_all(repo_path, paths)?;
+    commit_staged(repo_path, message)
 }
 
-/// Stage removals (deleted files) and create a commit.
-pub fn commit_removals(repo_path: &Path, paths: &[&str], message: &str) -> Result<(), RefstoreError> {
+/// Stage all changes under `paths`, including deletions.
+pub fn stage_all(repo_path: &Path, paths: &[&str]) -> Result<(), RefstoreError> {
     for path in paths {
-        // Use `git add -A` on the path to pick up deletions
+        // Use `git add -A` on the path to pick up deletions; a path that is gone
+        // entirely would fail the pathspec, so drop it from the index instead
         let mut cmd = Command::new("git");
-        cmd.args(["add", "-A", path]).current_dir(repo_path);
+        if repo_path.join(path).exists() {
+            cmd.args(["add", "-A", path]);
+        } else {
+            cmd.args(["rm", "-r", "-q", "--cached", "--ignore-unmatch", "--", path]);
+        }
+        cmd.current_dir(repo_path);
         let output = cmd.output().map_err(|_| RefstoreError::GitNotFound)?;
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(RefstoreError::GitCommand(stderr.to_string()));
         }
     }
+    Ok(())
+}
 
+/// Commit whatever is already staged. No-op if nothing is.
+pub fn commit_staged(repo_path: &Path, message: &str) -> Result<(), RefstoreError> {
     let status_output = Command::new("git")
         .args(["diff", "--cached", "--quiet"])
         .current_dir(repo_path)
@@ -167,6 +164,65 @@ pub fn submodule_update(repo_path: &Path, path: Option<&str>) -> Result<(), Refs
     run_git(repo_path, &args)
 }
 
+/// Fetch the submodule at `path` and check out `rev` there, detached. A branch
+/// name means the remote's latest commit on it. Records `rev` as the
+/// submodule's `ref` in `.gitmodules`.
+pub fn submodule_pin(repo_path: &Path, path: &str, rev: &str) -> Result<(), RefstoreError> {
+    let checkout = repo_path.join(path);
+    run_git(&checkout, &["fetch", "--quiet", "--tags", "origin"])?;
+    let commit = rev_parse(&checkout, &format!("origin/{rev}"))
+        .or_else(|_| rev_parse(&checkout, rev))
+        .map_err(|_| RefstoreError::GitCommand(format!("unknown revision '{rev}' in {path}")))?;
+    run_git(&checkout, &["checkout", "--quiet", "--detach", &commit])?;
+    run_git(repo_path, &["config", "-f", ".gitmodules", &format!("submodule.{path}.ref"), rev])
+}
+
+/// The URL `.gitmodules` records for the submodule at `path`.
+pub fn submodule_url(repo_path: &Path, path: &str) -> Result<Option<String>, RefstoreError> {
+    gitmodules_value(repo_path, path, "url")
+}
+
+/// The `ref` `.gitmodules` records for the submodule at `path`: the tag,
+/// branch, or commit `submodule_pin` pinned it to.
+pub fn submodule_ref(repo_path: &Path, path: &str) -> Result<Option<String>, RefstoreError> {
+    gitmodules_value(repo_path, path, "ref")
+}
+
+/// `submodule.<path>.<key>` from `.gitmodules`; `None` if it is unset or empty.
+fn gitmodules_value(repo_path: &Path, path: &str, key: &str) -> Result<Option<String>, RefstoreError> {
+    if !repo_path.join(".gitmodules").exists() {
+        return Ok(None);
+    }
+    let output = Command::new("git")
+        .args(["config", "-f", ".gitmodules", "--get", &format!("submodule.{path}.{key}")])
+        .current_dir(repo_path)
+        .output()
+        .map_err(|_| RefstoreError::GitNotFound)?;
+    match output.status.code() {
+        Some(0) => {
+            let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            Ok(Some(value).filter(|v| !v.is_empty()))
+        }
+        // `--get` exits with 1 when the key isn't set
+        Some(1) => Ok(None),
+        _ => Err(RefstoreError::GitCommand(String::from_utf8_lossy(&output.stderr).to_string())),
+    }
+}
+
+/// Tags on commits that build on `rev`, excluding tags of `rev` itself,
+/// highest version first.
+pub fn tags_after(repo_path: &Path, rev: &str) -> Result<Vec<String>, RefstoreError> {
+    let commit = rev_parse(repo_path, rev)?;
+    let at_rev = git_stdout(repo_path, &["tag", "--points-at", &commit])?;
+    let at_rev: HashSet<&str> = at_rev.lines().collect();
+    let containing = git_stdout(repo_path, &["tag", "--contains", &commit, "--sort=-v:refname"])?;
+    Ok(containing
+        .lines()
+        .filter(|tag| !at_rev.contains(tag))
+        .map(str::to_string)
+        .collect())
+}
+
 /// Remove `.git/` directory from a path, turning a git clone into plain files.
 pub fn strip_git_dir(path: &Path) -> Result<(), RefstoreError> {
     let git_dir = path.join(".git");
@@ -176,6 +232,27 @@ pub fn strip_git_dir(path: &Path) -> Result<(), RefstoreError> {
             source,
         })?;
     }
+
+    // Checked-out submodules have a `.git` file pointing into the parent's
+    // (now removed) `.git/modules/`.
+    let nested: Vec<_> = walkdir::WalkDir::new(path)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_name() == ".git")
+        .map(|e| e.into_path())
+        .collect();
+    for entry in nested {
+        let result = if entry.is_dir() {
+            fs::remove_dir_all(&entry)
+        } else {
+            fs::remove_file(&entry)
+        };
+        result.map_err(|source| RefstoreError::DirCreate {
+            path: entry,
+            source,
+        })?;
+    }
     Ok(())
 }
 
@@ -220,9 +297,74 @@ pub fn log_path(repo_path: &Path, path: &str) -> Result<Vec<LogEntry>, RefstoreE
     Ok(entries)
 }
 
+/// How a file changed between the last commit and the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A single file-level change from `git diff --cached`.
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub kind: ChangeKind,
+    /// Path relative to the diffed directory.
+    pub path: String,
+    /// Line counts; `None` for binary files.
+    pub lines_added: Option<usize>,
+    pub lines_removed: Option<usize>,
+}
+
+/// List staged changes under `path` (relative to `repo_path`).
+/// Returned paths have the `path/` prefix stripped.
+pub fn staged_changes(repo_path: &Path, path: &str) -> Result<Vec<FileChange>, RefstoreError> {
+    let name_status = git_stdout(
+        repo_path,
+        &["diff", "--cached", "--no-renames", "--name-status", "--", path],
+    )?;
+    let numstat = git_stdout(
+        repo_path,
+        &["diff", "--cached", "--no-renames", "--numstat", "--", path],
+    )?;
+
+    let mut counts = std::collections::HashMap::new();
+    for line in numstat.lines() {
+        let parts: Vec<&str> = line.splitn(3, '\t').collect();
+        if parts.len() == 3 {
+            counts.insert(parts[2], (parts[0].parse().ok(), parts[1].parse().ok()));
+        }
+    }
+
+    let prefix = format!("{}/", path.trim_end_matches('/'));
+    let changes = name_status
+        .lines()
+        .filter_map(|line| {
+            let (status, file) = line.split_once('\t')?;
+            let kind = match status {
+                "A" => ChangeKind::Added,
+                "D" => ChangeKind::Removed,
+                _ => ChangeKind::Modified,
+            };
+            let (lines_added, lines_removed) = counts.get(file).copied().unwrap_or((None, None));
+            Some(FileChange {
+                kind,
+                path: file.strip_prefix(&prefix).unwrap_or(file).to_string(),
+                lines_added,
+                lines_removed,
+            })
+        })
+        .collect();
+
+    Ok(changes)
+}
+
 /// Extract content at a specific git ref into a destination directory.
 /// `content_path` is the path within the repo (e.g., "content/my-ref").
 /// Files are extracted to `dest` with the `content_path` prefix stripped.
+///
+/// The archive stream is unpacked in-process; entries that would land
+/// outside `dest` (including symlinks pointing out of it) are rejected.
 pub fn archive_path_at_ref(
     repo_path: &Path,
     git_ref: &str,
@@ -234,47 +376,213 @@ pub fn archive_path_at_ref(
         source,
     })?;
 
-    // Count path components to strip (e.g., "content/my-ref" = 2)
-    let strip_components = content_path.split('/').count();
-
-    let git_archive = Command::new("git")
-        .args(["archive", git_ref, "--", content_path])
+    let mut git_archive = Command::new("git")
+        .args(["archive", "--format=tar", git_ref, "--", content_path])
         .current_dir(repo_path)
-        .stdout(std::process::Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()
         .map_err(|_| RefstoreError::GitNotFound)?;
 
-    let output = Command::new("tar")
-        .args(["x", &format!("--strip-components={strip_components}")])
-        .current_dir(dest)
-        .stdin(git_archive.stdout.unwrap())
-        .output()
-        .map_err(|e| RefstoreError::GitCommand(format!("tar extraction failed: {e}")))?;
+    let stdout = git_archive
+        .stdout
+        .take()
+        .expect("git archive stdout is piped");
+    let extracted = unpack_stripped(stdout, Path::new(content_path), dest).and_then(|()| verify_links(dest));
+    if extracted.is_err() {
+        // Stop git from blocking on a pipe nobody is reading any more.
+        let _ = git_archive.kill();
+    }
 
-    if !output.status.success() {
+    let output = git_archive
+        .wait_with_output()
+        .map_err(|e| RefstoreError::GitCommand(format!("git archive failed: {e}")))?;
+    if !output.status.success() && extracted.is_ok() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(RefstoreError::GitCommand(format!(
-            "failed to extract content at ref '{git_ref}': {stderr}"
+            "failed to archive '{content_path}' at ref '{git_ref}': {}",
+            stderr.trim()
         )));
     }
 
+    extracted.map_err(|e| {
+        RefstoreError::GitCommand(format!(
+            "failed to extract content at ref '{git_ref}': {e}"
+        ))
+    })
+}
+
+/// Unpack a tar stream into `dest`, keeping only entries under `prefix` and
+/// stripping it from their paths.
+fn unpack_stripped(reader: impl Read, prefix: &Path, dest: &Path) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        // Skips git's pax_global_header and anything outside the prefix.
+        let Ok(relative) = path.strip_prefix(prefix) else {
+            continue;
+        };
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(escape_error(&path));
+        }
+
+        let target = dest.join(relative);
+        if under_link(dest, relative) {
+            return Err(escape_error(&path));
+        }
+        let kind = entry.header().entry_type();
+        if kind.is_dir() {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+        if kind.is_symlink() {
+            let link = entry
+                .link_name()?
+                .ok_or_else(|| io::Error::other(format!("symlink '{}' has no target", path.display())))?;
+            if !link_stays_inside(relative, &link) {
+                return Err(escape_error(&path));
+            }
+        } else if !kind.is_file() {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&target)?;
+    }
     Ok(())
 }
 
-/// Check if a git ref (tag, branch, commit hash) exists in the repo.
-pub fn ref_exists(repo_path: &Path, git_ref: &str) -> bool {
-    Command::new("git")
-        .args(["rev-parse", "--verify", &format!("{git_ref}^{{commit}}")])
-        .current_dir(repo_path)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+/// Whether a directory on the way to `relative` is a symlink, so writing the
+/// entry would go through the link.
+fn under_link(dest: &Path, relative: &Path) -> bool {
+    let mut dir = dest.to_path_buf();
+    relative.parent().into_iter().flat_map(Path::components).any(|component| {
+        dir.push(component);
+        fs::symlink_metadata(&dir).is_ok_and(|m| m.file_type().is_symlink())
+    })
+}
+
+/// Check that every symlink extracted under `dest` resolves inside it,
+/// following chains through other extracted links. `link_stays_inside` only
+/// sees one link's text, so it can't catch `f -> d/x/..` with `d/x -> ..`.
+pub(crate) fn verify_links(dest: &Path) -> io::Result<()> {
+    for entry in walkdir::WalkDir::new(dest).min_depth(1) {
+        let entry = entry.map_err(io::Error::other)?;
+        if !entry.path_is_symlink() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dest).unwrap_or(entry.path());
+        if resolve_inside(dest, relative).is_none() {
+            return Err(escape_error(relative));
+        }
+    }
+    Ok(())
 }
 
-/// List tags in a repo.
-pub fn list_tags(repo_path: &Path) -> Result<Vec<String>, RefstoreError> {
+/// Most links followed while resolving one path, as in the kernel's ELOOP.
+const MAX_LINK_HOPS: usize = 40;
+
+/// Resolve the symlink at `relative` under `root` one component at a time,
+/// like the kernel does, so links along the way are followed too. Dangling
+/// targets resolve as far as they exist. `None` if the path would leave
+/// `root`, or for a loop or absolute link.
+fn resolve_inside(root: &Path, relative: &Path) -> Option<PathBuf> {
+    let mut resolved = PathBuf::new();
+    for component in relative.parent()?.components() {
+        resolved.push(component);
+    }
+    // A stack of components still to walk, next one last
+    let mut pending = Vec::new();
+    push_link(&mut pending, &fs::read_link(root.join(relative)).ok()?)?;
+
+    let mut hops = 0;
+    while let Some(component) = pending.pop() {
+        if component == ".." {
+            if !resolved.pop() {
+                return None;
+            }
+            continue;
+        }
+        resolved.push(&component);
+        if let Ok(link) = fs::read_link(root.join(&resolved)) {
+            hops += 1;
+            if hops > MAX_LINK_HOPS {
+                return None;
+            }
+            resolved.pop();
+            push_link(&mut pending, &link)?;
+        }
+    }
+    Some(resolved)
+}
+
+/// Queue a link's target on the `resolve_inside` stack; `None` if absolute.
+fn push_link(pending: &mut Vec<OsString>, link: &Path) -> Option<()> {
+    for component in link.components().rev() {
+        match component {
+            Component::Normal(c) => pending.push(c.to_os_string()),
+            Component::ParentDir => pending.push("..".into()),
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(())
+}
+
+/// Whether a symlink at `relative` (inside the extraction root) resolves to
+/// a path that is still inside the root.
+fn link_stays_inside(relative: &Path, link: &Path) -> bool {
+    let mut depth = relative.components().count() - 1;
+    for component in link.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+fn escape_error(path: &Path) -> io::Error {
+    io::Error::other(format!(
+        "refusing to extract '{}': it points outside the target directory",
+        path.display()
+    ))
+}
+
+/// A tag and the commit it points to.
+#[derive(Debug, Clone)]
+pub struct TagEntry {
+    pub name: String,
+    /// Abbreviated hash of the tagged commit
+    pub commit: String,
+    /// Tagger date for annotated tags, commit date otherwise
+    pub date: String,
+}
+
+/// List tags under `refs/tags/<prefix>`, e.g. all `my-docs/` tags, most
+/// recently created first.
+pub fn tags_with_prefix(repo_path: &Path, prefix: &str) -> Result<Vec<TagEntry>, RefstoreError> {
     let output = Command::new("git")
-        .args(["tag", "--sort=-creatordate"])
+        .args([
+            "for-each-ref",
+            "--sort=-creatordate",
+            "--format=%(refname:strip=2)%09%(if)%(*objectname)%(then)%(*objectname:short)\
+             %(else)%(objectname:short)%(end)%09%(creatordate:short)",
+            &format!("refs/tags/{prefix}"),
+        ])
         .current_dir(repo_path)
         .output()
         .map_err(|_| RefstoreError::GitNotFound)?;
@@ -286,8 +594,14 @@ pub fn list_tags(repo_path: &Path) -> Result<Vec<String>, RefstoreError> {
 
     Ok(String::from_utf8_lossy(&output.stdout)
         .lines()
-        .filter(|l| !l.is_empty())
-        .map(String::from)
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            Some(TagEntry {
+                name: parts.next()?.to_string(),
+                commit: parts.next()?.to_string(),
+                date: parts.next().unwrap_or("").to_string(),
+            })
+        })
         .collect())
 }
 
@@ -299,13 +613,254 @@ pub fn create_tag(repo_path: &Path, tag: &str, message: Option<&str>) -> Result<
     }
 }
 
+/// Determine whether `git_ref` names a branch, tag, or commit in the remote at `url`.
+/// Anything that isn't an advertised branch or tag but looks like a hex object id
+/// is treated as a commit.
+pub fn classify_ref(url: &str, git_ref: &str) -> Result<GitRefKind, RefstoreError> {
+    let output = Command::new("git")
+        .args(["-c", "protocol.file.allow=always", "ls-remote", url])
+        .output()
+        .map_err(|_| RefstoreError::GitNotFound)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RefstoreError::GitCommand(stderr.to_string()));
+    }
+
+    let branch = format!("refs/heads/{git_ref}");
+    let tag = format!("refs/tags/{git_ref}");
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((_, name)) = line.split_once('\t') else {
+            continue;
+        };
+        if name == branch {
+            return Ok(GitRefKind::Branch);
+        }
+        if name == tag || name.strip_suffix("^{}") == Some(tag.as_str()) {
+            return Ok(GitRefKind::Tag);
+        }
+    }
+
+    if (7..=40).contains(&git_ref.len()) && git_ref.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(GitRefKind::Commit);
+    }
+
+    Err(RefstoreError::GitCommand(format!(
+        "ref '{git_ref}' is not a branch, tag, or commit in {url}"
+    )))
+}
+
+/// Clone `url` and check out a specific commit.
+/// Servers don't reliably allow fetching an arbitrary commit by id, so this
+/// is a full clone; fixed commits are fetched once since updates skip them.
+pub fn clone_at_commit(
+    url: &str,
+    target: &Path,
+    commit: &str,
+    options: CheckoutOptions,
+) -> Result<(), RefstoreError> {
+    options.check()?;
+    let mut cmd = Command::new("git");
+    cmd.args(["clone", "--no-checkout", url]).arg(target);
+    options.apply_env(&mut cmd);
+    let output = cmd.output().map_err(|_| RefstoreError::GitNotFound)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RefstoreError::GitCommand(stderr.to_string()));
+    }
+
+    let mut cmd = Command::new("git");
+    cmd.args(["checkout", "--detach", commit]).current_dir(target);
+    options.apply_env(&mut cmd);
+    let output = cmd.output().map_err(|_| RefstoreError::GitNotFound)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RefstoreError::GitCommand(stderr.to_string()));
+    }
+
+    options.finish(url, target)
+}
+
+/// Create or refresh a bare clone of `url` at `cache_dir` holding all of its
+/// branches and tags. Later checkouts share its objects instead of re-cloning.
+pub fn update_mirror(url: &str, cache_dir: &Path) -> Result<(), RefstoreError> {
+    if cache_dir.join("HEAD").exists() {
+        // `--force` so a tag moved upstream is updated rather than rejected
+        return run_git(cache_dir, &["fetch", "--prune", "--tags", "--force", "origin"]);
+    }
+
+    if let Some(parent) = cache_dir.parent() {
+        fs::create_dir_all(parent).map_err(|source| RefstoreError::DirCreate {
+            path: parent.to_path_buf(),
+            source,
+        })?;
+    }
+    let output = Command::new("git")
+        .args(["clone", "--bare", url])
+        .arg(cache_dir)
+        .output()
+        .map_err(|_| RefstoreError::GitNotFound)?;
+    if !output.status.success() {
+        let _ = fs::remove_dir_all(cache_dir);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RefstoreError::GitCommand(stderr.to_string()));
+    }
+    // `clone --bare` sets no fetch refspec; keep branches updating in place
+    run_git(
+        cache_dir,
+        &["config", "remote.origin.fetch", "+refs/heads/*:refs/heads/*"],
+    )
+}
+
+/// Resolve `git_ref` (or the mirror's default branch) to a commit in a mirror
+/// made by `update_mirror`. Commits not reachable from any branch or tag are
+/// fetched on demand.
+pub fn resolve_in_mirror(
+    cache_dir: &Path,
+    url: &str,
+    git_ref: Option<&str>,
+    kind: Option<GitRefKind>,
+) -> Result<String, RefstoreError> {
+    let rev = git_ref.unwrap_or("HEAD");
+    if let Ok(commit) = rev_parse(cache_dir, rev) {
+        return Ok(commit);
+    }
+    if kind == Some(GitRefKind::Commit) && run_git(cache_dir, &["fetch", "origin", rev]).is_ok() {
+        return rev_parse(cache_dir, rev);
+    }
+    Err(RefstoreError::GitCommand(format!("ref '{rev}' not found in {url}")))
+}
+
+/// Check out `commit` from a mirror into `target`. The checkout borrows the
+/// mirror's objects, so nothing is downloaded except submodules and LFS files.
+pub fn checkout_from_mirror(
+    cache_dir: &Path,
+    url: &str,
+    commit: &str,
+    target: &Path,
+    options: CheckoutOptions,
+) -> Result<(), RefstoreError> {
+    options.check()?;
+    let output = Command::new("git")
+        .args(["clone", "--shared", "--no-checkout"])
+        .arg(cache_dir)
+        .arg(target)
+        .output()
+        .map_err(|_| RefstoreError::GitNotFound)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RefstoreError::GitCommand(stderr.to_string()));
+    }
+
+    // Relative submodule URLs and LFS resolve against origin, which must be
+    // the real upstream rather than the mirror.
+    run_git(target, &["remote", "set-url", "origin", url])?;
+
+    let mut cmd = Command::new("git");
+    cmd.args(["checkout", "--detach", commit]).current_dir(target);
+    options.apply_env(&mut cmd);
+    let output = cmd.output().map_err(|_| RefstoreError::GitNotFound)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RefstoreError::GitCommand(stderr.to_string()));
+    }
+
+    options.finish(url, target)
+}
+
+/// What to fetch beyond the plain checkout of a git source.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CheckoutOptions {
+    pub recurse_submodules: bool,
+    pub lfs: Option<LfsMode>,
+}
+
+impl CheckoutOptions {
+    /// Fail early if the options need tools that aren't installed.
+    fn check(&self) -> Result<(), RefstoreError> {
+        if self.lfs == Some(LfsMode::Fetch) {
+            let installed = Command::new("git")
+                .args(["lfs", "version"])
+                .output()
+                .is_ok_and(|o| o.status.success());
+            if !installed {
+                return Err(RefstoreError::GitCommand(
+                    "git-lfs is not installed; it is required for lfs = \"fetch\"".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checkouts never smudge LFS files when `lfs` is set: `skip` keeps the
+    /// pointers and `fetch` pulls the objects explicitly in `finish`.
+    fn apply_env(&self, cmd: &mut Command) {
+        if self.lfs.is_some() {
+            cmd.env("GIT_LFS_SKIP_SMUDGE", "1");
+        }
+    }
+
+    /// Check out submodules and LFS objects in a fresh clone of `url` at `target`.
+    fn finish(&self, url: &str, target: &Path) -> Result<(), RefstoreError> {
+        if self.recurse_submodules {
+            let mut cmd = Command::new("git");
+            // Submodules of a local repository are usually local too; git
+            // refuses the file transport for submodules unless told otherwise.
+            if is_local_url(url) {
+                cmd.args(["-c", "protocol.file.allow=always"]);
+            }
+            cmd.args(["submodule", "update", "--init", "--recursive"])
+                .current_dir(target);
+            self.apply_env(&mut cmd);
+            let output = cmd.output().map_err(|_| RefstoreError::GitNotFound)?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(RefstoreError::GitCommand(format!(
+                    "failed to fetch submodules of {url}: {}",
+                    stderr.trim()
+                )));
+            }
+
+            // A leading '-' marks a submodule that is still not checked out
+            let status = git_stdout(target, &["submodule", "status", "--recursive"])?;
+            let missing: Vec<&str> = status
+                .lines()
+                .filter(|l| l.starts_with('-'))
+                .filter_map(|l| l.split_whitespace().nth(1))
+                .collect();
+            if !missing.is_empty() {
+                return Err(RefstoreError::GitCommand(format!(
+                    "submodules of {url} were not checked out: {}",
+                    missing.join(", ")
+                )));
+            }
+        }
+
+        if self.lfs == Some(LfsMode::Fetch) {
+            run_git(target, &["lfs", "pull"])?;
+            if self.recurse_submodules {
+                run_git(target, &["submodule", "foreach", "--recursive", "git lfs pull"])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_local_url(url: &str) -> bool {
+    url.starts_with("file://") || !(url.contains("://") || url.starts_with("git@"))
+}
+
 pub fn clone_shallow(
     url: &str,
     target: &Path,
     git_ref: Option<&str>,
     depth: u32,
+    options: CheckoutOptions,
 ) -> Result<(), RefstoreError> {
+    options.check()?;
     let mut cmd = Command::new("git");
+    options.apply_env(&mut cmd);
     cmd.arg("clone");
     if depth > 0 {
         cmd.args(["--depth", &depth.to_string()]);
@@ -323,6 +878,40 @@ pub fn clone_shallow(
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(RefstoreError::GitCommand(stderr.to_string()));
     }
+    options.finish(url, target)
+}
+
+/// Resolve a revision (tag, branch, `HEAD~1`, abbreviated hash) to a full commit hash.
+pub fn rev_parse(repo_path: &Path, rev: &str) -> Result<String, RefstoreError> {
+    let spec = format!("{rev}^{{commit}}");
+    git_stdout(repo_path, &["rev-parse", "--verify", "--quiet", &spec])
+        .map(|s| s.trim().to_string())
+        .map_err(|_| RefstoreError::GitCommand(format!("unknown revision '{rev}'")))
+}
+
+/// Whether `path` exists in the tree of `rev`.
+pub fn has_path(repo_path: &Path, rev: &str, path: &str) -> bool {
+    git_stdout(repo_path, &["cat-file", "-e", &format!("{rev}:{path}")]).is_ok()
+}
+
+/// Restore `paths` in both the index and working tree to their state at `rev`,
+/// including deleting files (or whole paths) that did not exist at `rev`.
+pub fn restore_paths(repo_path: &Path, rev: &str, paths: &[&str]) -> Result<(), RefstoreError> {
+    let (present, absent): (Vec<&str>, Vec<&str>) = paths
+        .iter()
+        .partition(|p| has_path(repo_path, rev, p));
+
+    if !present.is_empty() {
+        let source = format!("--source={rev}");
+        let mut args = vec!["restore", source.as_str(), "--staged", "--worktree", "--"];
+        args.extend_from_slice(&present);
+        run_git(repo_path, &args)?;
+    }
+    if !absent.is_empty() {
+        let mut args = vec!["rm", "-r", "-q", "--ignore-unmatch", "--"];
+        args.extend_from_slice(&absent);
+        run_git(repo_path, &args)?;
+    }
     Ok(())
 }
 
@@ -345,6 +934,21 @@ pub fn is_git_repo(path: &Path) -> bool {
     path.join(".git").exists()
 }
 
+/// Run a git command and return its stdout.
+fn git_stdout(repo_path: &Path, args: &[&str]) -> Result<String, RefstoreError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(repo_path)
+        .output()
+        .map_err(|_| RefstoreError::GitNotFound)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RefstoreError::GitCommand(stderr.to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
 /// Run a git command and return an error if it fails.
 fn run_git(repo_path: &Path, args: &[&str]) -> Result<(), RefstoreError> {
     let output = Command::new("git")
@@ -4,9 +4,12 @@ use tracing_subscriber::EnvFilter;
 
 mod cli;
 mod error;
+mod extract;
 mod git;
 mod mcp;
 mod model;
+mod plugin;
+mod secrets;
 mod store;
 
 #[tokio::main]
@@ -57,11 +60,14 @@ async fn main() -> Result<()> {
             cli::sync::run(cli.data_dir.as_ref(), name, force)
         }
         cli::Command::Status => cli::status::run(cli.data_dir.as_ref()),
-        cli::Command::List { tag, kind } => cli::list::run(cli.data_dir.as_ref(), tag, kind),
+        cli::Command::List { tag, kind, shadowed } => {
+            cli::list::run(cli.data_dir.as_ref(), tag, kind, shadowed)
+        }
         cli::Command::Search { query, reference } => {
             cli::search::run(cli.data_dir.as_ref(), query, reference)
         }
         cli::Command::Info { name } => cli::info::run(cli.data_dir.as_ref(), name),
+        cli::Command::Outdated => cli::outdated::run(cli.data_dir.as_ref()),
         cli::Command::Versions { name } => cli::versions::run(cli.data_dir.as_ref(), name),
         cli::Command::Store(cmd) => cli::store::run(cli.data_dir.as_ref(), cmd),
         cli::Command::Bundle(cmd) => cli::bundle::run(cli.data_dir.as_ref(), cmd),
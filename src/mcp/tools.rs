@@ -6,7 +6,7 @@ use rmcp::{ServerHandler, tool, tool_handler, tool_router};
 
 use tokio::sync::Mutex;
 
-use crate::model::{ManifestEntry, McpScope};
+use crate::model::{ManifestEntry, McpScope, split_qualified};
 use crate::store::{ProjectStore, RepositoryStore};
 
 // Parameter types for each tool
@@ -19,13 +19,13 @@ pub struct ListReferencesParams {
 
 #[derive(Debug, Clone, serde::Deserialize, JsonSchema)]
 pub struct GetReferenceParams {
-    #[schemars(description = "Name of the reference")]
+    #[schemars(description = "Name of the reference, optionally qualified with its registry (team/react-docs)")]
     pub name: String,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, JsonSchema)]
 pub struct AddToProjectParams {
-    #[schemars(description = "Name of the reference to add")]
+    #[schemars(description = "Name of the reference to add, optionally qualified with its registry (team/react-docs)")]
     pub name: String,
 }
 
@@ -37,7 +37,7 @@ pub struct ListBundlesParams {
 
 #[derive(Debug, Clone, serde::Deserialize, JsonSchema)]
 pub struct GetBundleParams {
-    #[schemars(description = "Name of the bundle")]
+    #[schemars(description = "Name of the bundle, optionally qualified with its registry (team/frontend)")]
     pub name: String,
 }
 
@@ -118,7 +118,7 @@ Bundles cannot be added via MCP — the user adds them with:
 - **Central repository**: ~/.local/share/refstore/ — stores all reference content
 - **Project manifest**: refstore.toml in the project root — lists which refs to sync
 - **Synced content**: .references/<name>/ — read these with your filesystem tools
-- **Registries**: local registry is searched first, then remote registries alphabetically
+- **Registries**: local registry is searched first, then layered stores, then remote registries alphabetically; `refstore registry reorder` can change this
 
 ## Tips
 - Read `.references/` directly with your filesystem tools (Read, Grep, Glob)
@@ -292,8 +292,12 @@ Bundles cannot be added via MCP — the user adds them with:
             }
         };
 
-        let entry = ManifestEntry::default();
-        match project.add_reference(params.name.clone(), entry) {
+        let (registry, name) = split_qualified(&params.name);
+        let entry = ManifestEntry {
+            registry: registry.map(str::to_string),
+            ..Default::default()
+        };
+        match project.add_reference(name.to_string(), entry) {
             Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
                 "Added '{}' to project manifest. Run `refstore sync` to fetch content.",
                 params.name
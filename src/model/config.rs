@@ -2,21 +2,17 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+use super::layer::Layer;
 use super::registry::Registry;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum McpScope {
+    #[default]
     ReadOnly,
     ReadWrite,
 }
 
-impl Default for McpScope {
-    fn default() -> Self {
-        Self::ReadOnly
-    }
-}
-
 impl std::fmt::Display for McpScope {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -26,32 +22,172 @@ impl std::fmt::Display for McpScope {
     }
 }
 
+/// What to do with binary files (those containing NUL bytes) when fetching content.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryPolicy {
+    #[default]
+    Allow,
+    /// Store them, but list them after the fetch
+    Warn,
+    /// Leave them out of the stored content
+    Skip,
+}
+
+impl std::fmt::Display for BinaryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Allow => write!(f, "allow"),
+            Self::Warn => write!(f, "warn"),
+            Self::Skip => write!(f, "skip"),
+        }
+    }
+}
+
+impl std::str::FromStr for BinaryPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Self::Allow),
+            "warn" => Ok(Self::Warn),
+            "skip" => Ok(Self::Skip),
+            _ => Err(format!("unknown binary file policy '{s}' (expected allow, warn, or skip)")),
+        }
+    }
+}
+
+/// Which implementation runs git operations on the data directory.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitBackendKind {
+    /// The `git` command when it is on `PATH`, otherwise the in-process backend
+    #[default]
+    Auto,
+    /// Always shell out to `git`
+    Cli,
+    /// In-process (gitoxide). Git sources and registry submodules still
+    /// need the `git` command, and fail up front without it
+    Gix,
+}
+
+impl std::fmt::Display for GitBackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Cli => write!(f, "cli"),
+            Self::Gix => write!(f, "gix"),
+        }
+    }
+}
+
+impl std::str::FromStr for GitBackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "cli" => Ok(Self::Cli),
+            "gix" => Ok(Self::Gix),
+            _ => Err(format!("unknown git backend '{s}' (expected auto, cli, or gix)")),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data_dir: Option<PathBuf>,
     #[serde(default)]
     pub mcp_scope: McpScope,
+    /// Clone depth for git sources; only used when `git_cache` is off, as
+    /// the mirror keeps full history.
     #[serde(default = "default_depth")]
     pub git_depth: u32,
+    /// Keep a bare clone of each git upstream in `cache/git/` and fetch into
+    /// it incrementally. When off, every fetch is a fresh `git_depth` clone.
+    #[serde(default = "default_true")]
+    pub git_cache: bool,
+    #[serde(default)]
+    pub git_backend: GitBackendKind,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default_branch: Option<String>,
+    /// Store content files once in `objects/` and hardlink them into `content/`.
+    #[serde(default)]
+    pub dedup_content: bool,
+    /// Largest total size, in bytes, of one reference's stored content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_reference_size: Option<u64>,
+    /// Largest size, in bytes, of a single stored file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+    #[serde(default)]
+    pub binary_files: BinaryPolicy,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub registries: Vec<Registry>,
+    /// Read-only stores searched after the data directory, in order.
+    /// Overridden by `REFSTORE_PATH` when set.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub layers: Vec<Layer>,
 }
 
 fn default_depth() -> u32 {
     1
 }
 
+fn default_true() -> bool {
+    true
+}
+
 impl Default for GlobalConfig {
     fn default() -> Self {
         Self {
             data_dir: None,
             mcp_scope: McpScope::default(),
             git_depth: 1,
+            git_cache: true,
+            git_backend: GitBackendKind::default(),
             default_branch: None,
+            dedup_content: false,
+            max_reference_size: None,
+            max_file_size: None,
+            binary_files: BinaryPolicy::default(),
             registries: Vec::new(),
+            layers: Vec::new(),
+        }
+    }
+}
+
+impl GlobalConfig {
+    /// Resolution priority of a remote registry; 0 unless configured.
+    pub fn registry_priority(&self, name: &str) -> i32 {
+        self.registries.iter().find(|r| r.name == name).map_or(0, |r| r.priority)
+    }
+}
+
+const SIZE_UNITS: [(&str, u64); 4] = [("G", 1 << 30), ("M", 1 << 20), ("K", 1 << 10), ("", 1)];
+
+/// Parse a byte count such as `1048576`, `500K`, `20M`, or `2G` (binary units,
+/// an optional trailing `B` or `iB` is accepted).
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let upper = s.trim().to_ascii_uppercase();
+    let trimmed = upper.strip_suffix("IB").or_else(|| upper.strip_suffix('B')).unwrap_or(&upper);
+    for (suffix, factor) in SIZE_UNITS {
+        if let Some(number) = trimmed.strip_suffix(suffix)
+            && let Ok(n) = number.trim().parse::<u64>()
+        {
+            return n.checked_mul(factor).ok_or_else(|| format!("size '{s}' is too large"));
+        }
+    }
+    Err(format!("invalid size '{s}' (expected a byte count like 500K, 20M, or 2G)"))
+}
+
+/// Format a byte count with the largest binary unit that keeps it readable, e.g. `1.5 GiB`.
+pub fn format_size(bytes: u64) -> String {
+    for (suffix, factor) in SIZE_UNITS {
+        if !suffix.is_empty() && bytes >= factor {
+            return format!("{:.1} {suffix}iB", bytes as f64 / factor as f64);
         }
     }
+    format!("{bytes} B")
 }
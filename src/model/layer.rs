@@ -0,0 +1,11 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// An additional read-only store layered under the data directory, such as a
+/// shared `/etc/refstore` maintained by a platform team. Stored in config.toml.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Layer {
+    pub name: String,
+    pub path: PathBuf,
+}
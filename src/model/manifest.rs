@@ -3,6 +3,8 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+use super::migrate::MANIFEST_VERSION;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ManifestEntry {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -13,6 +15,21 @@ pub struct ManifestEntry {
     pub include: Vec<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub exclude: Vec<String>,
+    /// Take the reference from this registry instead of whichever one wins
+    /// the usual resolution order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+}
+
+impl ManifestEntry {
+    /// The name to look `name` up by in the store: registry-qualified
+    /// (`team/react-docs`) when the entry picks a registry.
+    pub fn lookup_name(&self, name: &str) -> String {
+        match &self.registry {
+            Some(registry) => format!("{registry}/{name}"),
+            None => name.to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,7 +55,7 @@ fn default_true() -> bool {
 impl Manifest {
     pub fn new(gitignore_references: bool) -> Self {
         Self {
-            version: 1,
+            version: MANIFEST_VERSION,
             gitignore_references,
             references: BTreeMap::new(),
             bundles: Vec::new(),
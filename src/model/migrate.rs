@@ -0,0 +1,89 @@
+//! Versioned on-disk formats for `index.toml` and `refstore.toml`.
+//!
+//! Files are parsed into a raw TOML table first, upgraded one version at a
+//! time, and only then deserialized, so a migration can rename or reshape
+//! fields that the current structs no longer accept.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::RefstoreError;
+
+/// Current `index.toml` format version.
+pub const INDEX_VERSION: u32 = 1;
+
+/// Current `refstore.toml` format version.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Upgrades a raw document by exactly one version.
+type Migration = fn(&mut toml::Table);
+
+/// `INDEX_MIGRATIONS[n]` upgrades an index from version `n` to `n + 1`.
+const INDEX_MIGRATIONS: &[Migration] = &[
+    // 0 -> 1: indexes created through `RepositoryIndex::default()` were
+    // stamped with version 0 but are otherwise identical to version 1.
+    |_| {},
+];
+
+/// `MANIFEST_MIGRATIONS[n]` upgrades a manifest from version `n` to `n + 1`.
+const MANIFEST_MIGRATIONS: &[Migration] = &[|_| {}];
+
+/// A document loaded at the current version, plus the version it was
+/// upgraded from (if any) so the caller knows to write it back.
+pub struct Loaded<T> {
+    pub value: T,
+    pub migrated_from: Option<u32>,
+}
+
+pub fn load_index<T: DeserializeOwned>(path: &Path, content: &str) -> Result<Loaded<T>, RefstoreError> {
+    load(path, content, "index", INDEX_VERSION, INDEX_MIGRATIONS)
+}
+
+pub fn load_manifest<T: DeserializeOwned>(path: &Path, content: &str) -> Result<Loaded<T>, RefstoreError> {
+    load(path, content, "manifest", MANIFEST_VERSION, MANIFEST_MIGRATIONS)
+}
+
+fn load<T: DeserializeOwned>(
+    path: &Path,
+    content: &str,
+    kind: &'static str,
+    current: u32,
+    migrations: &[Migration],
+) -> Result<Loaded<T>, RefstoreError> {
+    debug_assert_eq!(migrations.len(), current as usize);
+
+    let mut table: toml::Table = toml::from_str(content)?;
+
+    // Files written before versions were recorded are version 1.
+    let found = match table.get("version") {
+        None => 1,
+        Some(v) => v
+            .as_integer()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| RefstoreError::InvalidFormatVersion {
+                path: path.to_path_buf(),
+                kind,
+                found: v.to_string(),
+            })?,
+    };
+
+    if found > current {
+        return Err(RefstoreError::UnsupportedFormatVersion {
+            path: path.to_path_buf(),
+            kind,
+            found,
+            supported: current,
+        });
+    }
+
+    for migration in &migrations[found as usize..] {
+        migration(&mut table);
+    }
+    table.insert("version".to_string(), toml::Value::Integer(current.into()));
+
+    Ok(Loaded {
+        value: table.try_into()?,
+        migrated_from: (found < current).then_some(found),
+    })
+}
@@ -1,13 +1,20 @@
 pub mod bundle;
 pub mod config;
+pub mod layer;
 pub mod manifest;
+pub mod migrate;
 pub mod reference;
 pub mod registry;
 pub mod repository;
+pub mod sync_state;
 
 pub use bundle::Bundle;
-pub use config::{GlobalConfig, McpScope};
+pub use config::{BinaryPolicy, GitBackendKind, GlobalConfig, McpScope};
+pub use layer::Layer;
 pub use manifest::{Manifest, ManifestEntry};
-pub use reference::{Reference, ReferenceKind, ReferenceSource};
+pub use reference::{
+    BuildStep, ExtractFormat, GitRefKind, LfsMode, Reference, ReferenceKind, ReferenceSource, split_qualified,
+};
 pub use registry::Registry;
 pub use repository::RepositoryIndex;
+pub use sync_state::{SyncState, SyncedVersion};
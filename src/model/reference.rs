@@ -9,6 +9,7 @@ pub enum ReferenceKind {
     File,
     Directory,
     GitRepo,
+    Snippet,
 }
 
 impl std::fmt::Display for ReferenceKind {
@@ -17,6 +18,57 @@ impl std::fmt::Display for ReferenceKind {
             Self::File => write!(f, "file"),
             Self::Directory => write!(f, "directory"),
             Self::GitRepo => write!(f, "git_repo"),
+            Self::Snippet => write!(f, "snippet"),
+        }
+    }
+}
+
+/// What a git source's ref points at: a moving branch or a fixed tag/commit.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitRefKind {
+    Branch,
+    Tag,
+    Commit,
+}
+
+impl std::fmt::Display for GitRefKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Branch => write!(f, "branch"),
+            Self::Tag => write!(f, "tag"),
+            Self::Commit => write!(f, "commit"),
+        }
+    }
+}
+
+/// Whether Git LFS objects are downloaded or left as pointer files.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LfsMode {
+    /// Keep LFS pointer files; never download LFS objects.
+    Skip,
+    /// Download LFS objects (requires git-lfs).
+    Fetch,
+}
+
+impl std::fmt::Display for LfsMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Skip => write!(f, "skip"),
+            Self::Fetch => write!(f, "fetch"),
+        }
+    }
+}
+
+impl std::str::FromStr for LfsMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "fetch" => Ok(Self::Fetch),
+            _ => Err(format!("unknown lfs mode '{s}' (expected skip or fetch)")),
         }
     }
 }
@@ -33,10 +85,28 @@ pub enum ReferenceSource {
         r#ref: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         subpath: Option<PathBuf>,
+        /// Resolved when the reference is first fetched; `None` for entries
+        /// written by older versions until their next update.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ref_kind: Option<GitRefKind>,
+        /// Check out submodules (recursively) along with the repository.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        recurse_submodules: bool,
+        /// How Git LFS files are handled; `None` leaves it to the user's git config.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lfs: Option<LfsMode>,
     },
     Remote {
         url: String,
     },
+    /// Text written directly into the registry; there is nothing to fetch.
+    Inline,
+    /// Fetched by the external `refstore-source-<scheme>` helper.
+    Plugin {
+        scheme: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+    },
 }
 
 impl std::fmt::Display for ReferenceSource {
@@ -51,10 +121,80 @@ impl std::fmt::Display for ReferenceSource {
                 Ok(())
             }
             Self::Remote { url } => write!(f, "{url}"),
+            Self::Inline => write!(f, "(inline snippet)"),
+            Self::Plugin { scheme, args } => {
+                write!(f, "{scheme}::")?;
+                write!(f, "{}", args.join(" "))
+            }
         }
     }
 }
 
+/// Derived formats generated next to fetched content so text tools can read it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractFormat {
+    /// `.pdf` files get a `.pdf.md` sibling with page markers.
+    Pdf,
+    /// `.ipynb` notebooks get a `.ipynb.md` sibling with fenced code cells.
+    Notebook,
+}
+
+impl std::fmt::Display for ExtractFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pdf => write!(f, "pdf"),
+            Self::Notebook => write!(f, "notebook"),
+        }
+    }
+}
+
+impl std::str::FromStr for ExtractFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pdf" => Ok(Self::Pdf),
+            "notebook" | "ipynb" => Ok(Self::Notebook),
+            _ => Err(format!("unknown extract format '{s}' (expected pdf or notebook)")),
+        }
+    }
+}
+
+impl ReferenceSource {
+    /// Whether this source is a git tag or commit, which never changes upstream.
+    pub fn is_fixed_ref(&self) -> bool {
+        matches!(
+            self,
+            Self::Git {
+                ref_kind: Some(GitRefKind::Tag | GitRefKind::Commit),
+                ..
+            }
+        )
+    }
+
+    /// Describe what a git source follows, e.g. "tracking main" or "fixed at tag v1.0".
+    /// `default_branch` names the branch used when the source has no ref.
+    pub fn tracking_label(&self, default_branch: Option<&str>) -> Option<String> {
+        let Self::Git {
+            r#ref,
+            ref_kind: Some(kind),
+            ..
+        } = self
+        else {
+            return None;
+        };
+        let r = r#ref.as_deref();
+        Some(match kind {
+            GitRefKind::Branch => {
+                format!("tracking {}", r.or(default_branch).unwrap_or("default branch"))
+            }
+            GitRefKind::Tag => format!("fixed at tag {}", r.unwrap_or_default()),
+            GitRefKind::Commit => format!("fixed at commit {:.8}", r.unwrap_or_default()),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Reference {
     pub name: String,
@@ -69,4 +209,29 @@ pub struct Reference {
     pub last_synced: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub checksum: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extract: Vec<ExtractFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<BuildStep>,
+}
+
+/// A command run on a fresh checkout at fetch time; only `output` is kept.
+/// The command gets a cleared environment. Sandboxing is not implemented: it
+/// runs with the user's filesystem permissions and network access.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BuildStep {
+    /// Shell command, run from the checkout root.
+    pub cmd: String,
+    /// File or directory (relative to the checkout root) cached as the content.
+    pub output: PathBuf,
+}
+
+/// Split a registry-qualified name like `team/react-docs` into the registry
+/// and the reference name. Names can't contain `/`, so a plain name has no
+/// registry.
+pub fn split_qualified(name: &str) -> (Option<&str>, &str) {
+    match name.split_once('/') {
+        Some((registry, name)) => (Some(registry), name),
+        None => (None, name),
+    }
 }
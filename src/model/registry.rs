@@ -5,4 +5,16 @@ use serde::{Deserialize, Serialize};
 pub struct Registry {
     pub name: String,
     pub url: String,
+    /// Tag, branch, or commit the registry is pinned to; `None` tracks the
+    /// remote's default branch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r#ref: Option<String>,
+    /// Registries with a higher priority are searched first. The local store
+    /// and layers sit at 0, ahead of remotes of equal priority.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i32,
+}
+
+fn is_zero(n: &i32) -> bool {
+    *n == 0
 }
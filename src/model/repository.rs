@@ -3,9 +3,10 @@ use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 
 use super::bundle::Bundle;
+use super::migrate::INDEX_VERSION;
 use super::reference::Reference;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RepositoryIndex {
     #[serde(default = "default_version")]
     pub version: u32,
@@ -18,3 +19,13 @@ pub struct RepositoryIndex {
 fn default_version() -> u32 {
     1
 }
+
+impl Default for RepositoryIndex {
+    fn default() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            references: BTreeMap::new(),
+            bundles: BTreeMap::new(),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::manifest::ManifestEntry;
+
+/// What each pinned reference in `.references/` was last synced from, so that
+/// `sync` can skip entries that already hold the resolved version.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    #[serde(default)]
+    pub references: BTreeMap<String, SyncedVersion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SyncedVersion {
+    pub commit: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl SyncedVersion {
+    /// The state left by syncing `commit` with `entry`'s filters.
+    pub fn new(commit: &str, entry: &ManifestEntry) -> Self {
+        Self {
+            commit: commit.to_string(),
+            include: entry.include.clone(),
+            exclude: entry.exclude.clone(),
+        }
+    }
+}
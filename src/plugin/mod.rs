@@ -0,0 +1,115 @@
+//! External source helpers, modelled on git remote helpers.
+//!
+//! A reference with `source = { type = "plugin", scheme = "wiki", args = [...] }`
+//! is fetched by running `refstore-source-wiki` from `PATH`. refstore writes a
+//! request to the helper's stdin, one `key value` pair per line, ending with a
+//! blank line:
+//!
+//! ```text
+//! refstore-source 1
+//! fetch <reference name>
+//! target <absolute path of an empty directory>
+//! arg <value>              (once per argument, in order)
+//! previous <version>       (only when a previous fetch reported one)
+//!
+//! ```
+//!
+//! Values can't contain control characters such as newlines, so every pair
+//! stays on its own line; a request with one is refused before the helper runs.
+//!
+//! The helper writes the reference's files into `target` and replies on stdout
+//! with optional `version <string>` and a final `ok` line, or with
+//! `error <message>`. Unknown lines are ignored. Anything on stderr is shown to
+//! the user if the helper fails.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::error::RefstoreError;
+
+/// Protocol version written on the first line of every request.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Name of the executable that handles `scheme`.
+pub fn helper_name(scheme: &str) -> String {
+    format!("refstore-source-{scheme}")
+}
+
+/// Run the helper for `scheme` to fill `target`. Returns the version it reported.
+pub fn fetch(
+    scheme: &str,
+    args: &[String],
+    name: &str,
+    target: &Path,
+    previous: Option<&str>,
+) -> Result<Option<String>, RefstoreError> {
+    let helper = helper_name(scheme);
+    let failed = |reason: String| RefstoreError::PluginFailed {
+        helper: helper.clone(),
+        reason,
+    };
+
+    // The helper runs from `target`, so a relative path would be wrong there
+    let target = std::path::absolute(target)
+        .map_err(|e| failed(format!("cannot resolve {}: {e}", target.display())))?;
+    let target = target.as_path();
+
+    let mut request = format!("refstore-source {PROTOCOL_VERSION}\n");
+    let target_line = target.display().to_string();
+    let pairs = [("fetch", name), ("target", target_line.as_str())]
+        .into_iter()
+        .chain(args.iter().map(|arg| ("arg", arg.as_str())))
+        .chain(previous.map(|previous| ("previous", previous)));
+    for (key, value) in pairs {
+        if value.chars().any(char::is_control) {
+            return Err(failed(format!("{key} {value:?} contains a control character")));
+        }
+        request.push_str(&format!("{key} {value}\n"));
+    }
+    request.push('\n');
+
+    let mut child = Command::new(&helper)
+        .current_dir(target)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => failed("not found on PATH".to_string()),
+            _ => failed(e.to_string()),
+        })?;
+
+    // A helper that exits without reading its request closes the pipe early;
+    // its exit status and reply are what matter, so ignore write errors.
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(request.as_bytes());
+    }
+    let output = child.wait_with_output().map_err(|e| failed(e.to_string()))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut version = None;
+    let mut ok = false;
+    for line in stdout.lines() {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "version" if !value.is_empty() => version = Some(value.to_string()),
+            "ok" => ok = true,
+            "error" => return Err(failed(value.to_string())),
+            _ => {}
+        }
+    }
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failed(format!("exited with {}: {}", output.status, stderr.trim())));
+    }
+    if !ok {
+        return Err(failed("did not reply `ok`".to_string()));
+    }
+    let empty = target.read_dir().map_or(true, |mut d| d.next().is_none());
+    if empty {
+        return Err(failed("wrote no files".to_string()));
+    }
+    Ok(version)
+}
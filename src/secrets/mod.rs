@@ -0,0 +1,164 @@
+//! Credential scanning run before fetched content is committed to a registry.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+
+use crate::error::RefstoreError;
+
+/// Per-registry allowlist, at the registry root next to `index.toml`.
+///
+/// One entry per line: a glob matched against the finding's path within the
+/// registry (e.g. `content/my-docs/tests/fixtures/**`), optionally followed by
+/// a rule id to allow only that rule. `#` starts a comment.
+pub const ALLOWLIST_FILE: &str = "secrets-allowlist";
+
+/// A line that looks like it contains a credential.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    /// Path within the registry, e.g. `content/my-docs/.env`
+    pub path: PathBuf,
+    /// 1-based line number
+    pub line: usize,
+    pub rule: &'static str,
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.rule)
+    }
+}
+
+struct Rule {
+    id: &'static str,
+    pattern: Regex,
+    /// Only applies to dotenv files
+    env_only: bool,
+}
+
+static RULES: LazyLock<Vec<Rule>> = LazyLock::new(|| {
+    let rule = |id, pattern: &str, env_only| Rule {
+        id,
+        pattern: Regex::new(pattern).expect("valid secret pattern"),
+        env_only,
+    };
+    vec![
+        rule(
+            "private-key",
+            r"-----BEGIN (?:RSA |EC |DSA |OPENSSH |PGP |ENCRYPTED )?PRIVATE KEY(?: BLOCK)?-----",
+            false,
+        ),
+        rule("aws-access-key-id", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b", false),
+        rule(
+            "aws-secret-access-key",
+            r#"(?i)aws_?secret_?access_?key["']?\s*[:=]\s*["']?[A-Za-z0-9/+=]{40}\b"#,
+            false,
+        ),
+        rule("github-token", r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})\b", false),
+        rule("slack-token", r"\bxox[abprs]-[A-Za-z0-9-]{10,}", false),
+        rule(
+            "env-secret",
+            r#"^\s*(?:export\s+)?[A-Za-z0-9_]*(?i:secret|token|passw(?:or)?d|api_?key|private_?key|access_?key)[A-Za-z0-9_]*\s*=\s*["']?[^\s"'$<#]{4,}"#,
+            true,
+        ),
+    ]
+});
+
+pub struct Scanner {
+    allow: Vec<(GlobMatcher, Option<String>)>,
+}
+
+impl Scanner {
+    /// Load the allowlist of the registry at `registry_root`, if it has one.
+    pub fn for_registry(registry_root: &Path) -> Result<Self, RefstoreError> {
+        let path = registry_root.join(ALLOWLIST_FILE);
+        let mut allow = Vec::new();
+        if path.exists() {
+            let content = fs::read_to_string(&path).map_err(|source| RefstoreError::FileRead {
+                path: path.clone(),
+                source,
+            })?;
+            for line in content.lines() {
+                let line = line.split('#').next().unwrap_or("").trim();
+                let mut parts = line.split_whitespace();
+                let Some(pattern) = parts.next() else {
+                    continue;
+                };
+                let glob = Glob::new(pattern).map_err(|e| RefstoreError::InvalidAllowlist {
+                    path: path.clone(),
+                    reason: e.to_string(),
+                })?;
+                allow.push((glob.compile_matcher(), parts.next().map(str::to_string)));
+            }
+        }
+        Ok(Self { allow })
+    }
+
+    /// Scan every text file under `dir`. Reported paths are `prefix` joined
+    /// with the path relative to `dir`, i.e. where the file lives in the registry.
+    pub fn scan_dir(&self, dir: &Path, prefix: &Path) -> Result<Vec<Finding>, RefstoreError> {
+        let mut findings = Vec::new();
+        for entry in walkdir::WalkDir::new(dir).min_depth(1).sort_by_file_name() {
+            let entry = entry.map_err(|e| RefstoreError::FileRead {
+                path: dir.to_path_buf(),
+                source: e.into(),
+            })?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let bytes = fs::read(entry.path()).map_err(|source| RefstoreError::FileRead {
+                path: entry.path().to_path_buf(),
+                source,
+            })?;
+            // Binary files don't hold credentials we can find line by line
+            if bytes.iter().take(8192).any(|&b| b == 0) {
+                continue;
+            }
+
+            let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+            findings.extend(self.scan_text(&String::from_utf8_lossy(&bytes), &prefix.join(relative)));
+        }
+        Ok(findings)
+    }
+
+    /// Scan `text`, to be stored at `path` within the registry.
+    pub fn scan_text(&self, text: &str, path: &Path) -> Vec<Finding> {
+        let env_file = is_env_file(path);
+        let mut findings = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            for rule in RULES.iter() {
+                if rule.env_only && !env_file {
+                    continue;
+                }
+                if rule.pattern.is_match(line) && !self.is_allowed(path, rule.id) {
+                    findings.push(Finding {
+                        path: path.to_path_buf(),
+                        line: index + 1,
+                        rule: rule.id,
+                    });
+                }
+            }
+        }
+        findings
+    }
+
+    fn is_allowed(&self, path: &Path, rule: &str) -> bool {
+        self.allow
+            .iter()
+            .any(|(glob, only)| glob.is_match(path) && only.as_deref().is_none_or(|r| r == rule))
+    }
+}
+
+/// `.env`, `.env.local`, `prod.env`, but not templates like `.env.example`.
+fn is_env_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let template = [".example", ".sample", ".template", ".dist"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix));
+    !template && (name == ".env" || name.starts_with(".env.") || name.ends_with(".env"))
+}
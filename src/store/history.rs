@@ -0,0 +1,69 @@
+use crate::git::LogEntry;
+
+/// A store mutation recovered from a data-dir commit message such as
+/// "Add reference: my-docs" or "Update bundle: rust-stack".
+#[derive(Debug, Clone)]
+pub struct Operation {
+    /// "add", "remove", "update" or "undo"
+    pub action: String,
+    /// "reference", "bundle", "registry", or "store" for whole-store operations
+    pub target: String,
+    pub name: String,
+    /// Anything after the name, e.g. an update's change summary
+    pub detail: Option<String>,
+}
+
+/// A commit in the data dir, with its parsed operation when recognised.
+pub struct HistoryEntry {
+    pub log: LogEntry,
+    pub operation: Option<Operation>,
+}
+
+impl HistoryEntry {
+    pub fn from_log(log: LogEntry) -> Self {
+        let operation = parse_operation(&log.message);
+        Self { log, operation }
+    }
+}
+
+/// Parse a commit subject written by `RepositoryStore`.
+pub fn parse_operation(message: &str) -> Option<Operation> {
+    if let Some(rev) = message.strip_prefix("Undo: restore store to ") {
+        return Some(Operation {
+            action: "undo".to_string(),
+            target: "store".to_string(),
+            name: rev.to_string(),
+            detail: None,
+        });
+    }
+    if message == "Update all registries" {
+        return Some(Operation {
+            action: "update".to_string(),
+            target: "registry".to_string(),
+            name: "*".to_string(),
+            detail: None,
+        });
+    }
+
+    let (head, rest) = message.split_once(": ")?;
+    let (action, target) = head.split_once(' ')?;
+    let action = match action {
+        "Add" | "Remove" | "Update" => action.to_lowercase(),
+        _ => return None,
+    };
+    if !matches!(target, "reference" | "bundle" | "registry") {
+        return None;
+    }
+
+    let (name, detail) = match rest.split_once(" (") {
+        Some((name, detail)) => (name, Some(detail.trim_end_matches(')').to_string())),
+        None => (rest, None),
+    };
+
+    Some(Operation {
+        action,
+        target: target.to_string(),
+        name: name.to_string(),
+        detail,
+    })
+}
@@ -0,0 +1,200 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::RefstoreError;
+use crate::model::RepositoryIndex;
+
+use super::registry::RegistryStore;
+
+/// Bump when the layout below changes; older caches are then rebuilt.
+const CACHE_VERSION: u32 = 1;
+
+/// Lookup tables over every registry in resolution order. Registries are
+/// identified by their position in that order.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MergedIndex {
+    /// The registry whose copy of each reference wins.
+    names: HashMap<String, usize>,
+    /// Names of the winning references carrying each tag, in listing order.
+    tags: HashMap<String, Vec<String>>,
+    /// All winning names in listing order: by registry, then by name.
+    listing: Vec<String>,
+    /// Names defined by more than one registry, with every registry that
+    /// defines them; the first one wins.
+    shadowed: BTreeMap<String, Vec<usize>>,
+}
+
+impl MergedIndex {
+    pub fn build<'a>(stores: impl Iterator<Item = &'a RegistryStore>) -> Self {
+        let mut merged = Self::default();
+        for (position, store) in stores.enumerate() {
+            for reference in store.list(None, None) {
+                let name = &reference.name;
+                match merged.names.get(name) {
+                    Some(&winner) => {
+                        merged.shadowed.entry(name.clone()).or_insert_with(|| vec![winner]).push(position)
+                    }
+                    None => {
+                        merged.names.insert(name.clone(), position);
+                        merged.listing.push(name.clone());
+                        for tag in &reference.tags {
+                            merged.tags.entry(tag.clone()).or_default().push(name.clone());
+                        }
+                    }
+                }
+            }
+        }
+        merged
+    }
+
+    /// Position of the registry whose copy of `name` wins.
+    pub fn winner(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    /// Names defined by more than one registry, sorted, with the positions of
+    /// the registries defining them; the first one wins.
+    pub fn shadowed(&self) -> impl Iterator<Item = (&str, &[usize])> {
+        self.shadowed.iter().map(|(name, positions)| (name.as_str(), positions.as_slice()))
+    }
+
+    /// Winning names in listing order, optionally only those tagged `tag`.
+    pub fn names(&self, tag: Option<&str>) -> &[String] {
+        match tag {
+            Some(tag) => self.tags.get(tag).map(Vec::as_slice).unwrap_or_default(),
+            None => &self.listing,
+        }
+    }
+}
+
+/// `M` is the merged index, owned when loading and borrowed when saving.
+#[derive(Serialize, Deserialize)]
+struct CacheFile<M> {
+    version: u32,
+    registries: Vec<CachedRegistry>,
+    merged: M,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedRegistry {
+    name: String,
+    root: PathBuf,
+    /// `index.toml` as it was when parsed; `None` if there was none.
+    fingerprint: Option<Fingerprint>,
+    migrated_from: Option<u32>,
+    index: RepositoryIndex,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Fingerprint {
+    len: u64,
+    /// Modification time as seconds and nanoseconds since the epoch, if the
+    /// platform reports one.
+    modified: Option<(u64, u32)>,
+    sha256: String,
+}
+
+impl Fingerprint {
+    fn take(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        let content = fs::read(path).ok()?;
+        Some(Self {
+            len: meta.len(),
+            modified: modified(&meta),
+            sha256: Sha256::digest(&content).iter().map(|b| format!("{b:02x}")).collect(),
+        })
+    }
+}
+
+fn modified(meta: &fs::Metadata) -> Option<(u64, u32)> {
+    let since_epoch = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((since_epoch.as_secs(), since_epoch.subsec_nanos()))
+}
+
+/// Whether the index file at `path` is still the one `stored` was taken
+/// from. Only re-hashes the file when its size or mtime moved, or when its
+/// mtime isn't older than `written`, the cache file's own mtime: like git's
+/// racy-clean check, a write within the same timestamp tick as the cache
+/// could otherwise go unnoticed.
+fn unchanged(stored: &Option<Fingerprint>, path: &Path, written: Option<(u64, u32)>) -> bool {
+    let meta = fs::metadata(path).ok();
+    match (stored, meta) {
+        (None, None) => true,
+        (Some(stored), Some(meta))
+            if stored.len == meta.len()
+                && stored.modified.is_some_and(|m| written.is_some_and(|w| m < w))
+                && stored.modified == modified(&meta) =>
+        {
+            true
+        }
+        (Some(stored), Some(_)) => Fingerprint::take(path).is_some_and(|now| now.sha256 == stored.sha256),
+        _ => false,
+    }
+}
+
+fn cache_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("cache").join("index.json")
+}
+
+/// The registries and merged index cached for `data_dir`, if the cache
+/// covers exactly `registries` (name and root, in resolution order) and none
+/// of their `index.toml` files changed since.
+pub fn load(data_dir: &Path, registries: &[(String, PathBuf)]) -> Option<(Vec<RegistryStore>, MergedIndex)> {
+    let path = cache_path(data_dir);
+    let written = fs::metadata(&path).ok().as_ref().and_then(modified);
+    let content = fs::read(&path).ok()?;
+    let cache: CacheFile<MergedIndex> = serde_json::from_slice(&content).ok()?;
+    if cache.version != CACHE_VERSION || cache.registries.len() != registries.len() {
+        return None;
+    }
+
+    let fresh = cache.registries.iter().zip(registries).all(|(cached, (name, root))| {
+        cached.name == *name && cached.root == *root && unchanged(&cached.fingerprint, &root.join("index.toml"), written)
+    });
+    if !fresh {
+        return None;
+    }
+
+    let stores = cache
+        .registries
+        .into_iter()
+        .map(|r| RegistryStore::from_index(r.root, r.index, r.migrated_from))
+        .collect();
+    Some((stores, cache.merged))
+}
+
+/// Write the cache for `registries`, given in resolution order with `merged`
+/// built from them.
+pub fn save(
+    data_dir: &Path,
+    registries: &[(&str, &RegistryStore)],
+    merged: &MergedIndex,
+) -> Result<(), RefstoreError> {
+    let cache = CacheFile {
+        version: CACHE_VERSION,
+        registries: registries
+            .iter()
+            .map(|(name, store)| CachedRegistry {
+                name: name.to_string(),
+                root: store.root().to_path_buf(),
+                fingerprint: Fingerprint::take(&store.root().join("index.toml")),
+                migrated_from: store.migrated_from(),
+                index: store.index().clone(),
+            })
+            .collect(),
+        merged,
+    };
+    let path = cache_path(data_dir);
+    let dir = data_dir.join("cache");
+    fs::create_dir_all(&dir).map_err(|source| RefstoreError::DirCreate { path: dir, source })?;
+    let content = serde_json::to_vec(&cache).map_err(|e| RefstoreError::FileWrite {
+        path: path.clone(),
+        source: e.into(),
+    })?;
+    fs::write(&path, content).map_err(|source| RefstoreError::FileWrite { path, source })
+}
@@ -1,7 +1,15 @@
+pub mod history;
+pub mod index_cache;
+pub mod objects;
 pub mod project;
+pub mod quota;
 pub mod registry;
+pub mod release;
 pub mod repository;
 
 pub use project::ProjectStore;
 pub use registry::RegistryStore;
 pub use repository::RepositoryStore;
+
+#[cfg(test)]
+mod tests;
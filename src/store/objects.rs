@@ -0,0 +1,186 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::RefstoreError;
+
+/// Content-addressed file store at `<data_dir>/objects/<aa>/<rest-of-sha256>`.
+///
+/// Files under `content/` (and remote registries' `content/`) are replaced by
+/// hardlinks to their object, so identical files are stored once on disk while
+/// every path keeps looking like a plain file to git, sync, and MCP. Content
+/// files must therefore never be modified in place: writers replace them.
+pub struct ObjectStore {
+    root: PathBuf,
+}
+
+/// What a dedup or gc pass did.
+#[derive(Debug, Default)]
+pub struct ObjectStats {
+    /// Regular files visited
+    pub files: usize,
+    /// Files that were linked to an object another file already provided
+    pub deduplicated: usize,
+    /// Bytes no longer stored twice thanks to `deduplicated`
+    pub bytes_saved: u64,
+    /// Objects removed because nothing referenced them any more
+    pub pruned: usize,
+    /// Bytes held by the remaining objects
+    pub object_bytes: u64,
+    /// Cached version extractions removed because their reference is gone
+    pub versions_pruned: usize,
+}
+
+impl ObjectStore {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            root: data_dir.join("objects"),
+        }
+    }
+
+    /// Replace every regular file under `dir` with a hardlink to its object,
+    /// creating objects for content not seen before. Hashes of all visited
+    /// files are added to `seen`.
+    pub fn dedup_dir(
+        &self,
+        dir: &Path,
+        seen: &mut HashSet<String>,
+        stats: &mut ObjectStats,
+    ) -> Result<(), RefstoreError> {
+        if !dir.exists() {
+            return Ok(());
+        }
+        for entry in walkdir::WalkDir::new(dir).min_depth(1) {
+            let entry = entry.map_err(|e| RefstoreError::FileRead {
+                path: dir.to_path_buf(),
+                source: e.into(),
+            })?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            self.link_file(entry.path(), seen, stats)?;
+        }
+        Ok(())
+    }
+
+    /// Delete objects whose hash is not in `keep`. Files that still link to a
+    /// deleted object keep their data; they just stop sharing it.
+    pub fn prune(&self, keep: &HashSet<String>, stats: &mut ObjectStats) -> Result<(), RefstoreError> {
+        if !self.root.exists() {
+            return Ok(());
+        }
+        for entry in walkdir::WalkDir::new(&self.root).min_depth(2).max_depth(2) {
+            let entry = entry.map_err(|e| RefstoreError::FileRead {
+                path: self.root.clone(),
+                source: e.into(),
+            })?;
+            let path = entry.path();
+            let hash = object_hash(path);
+            if keep.contains(&hash) {
+                stats.object_bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+                continue;
+            }
+            fs::remove_file(path).map_err(|source| RefstoreError::FileWrite {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            stats.pruned += 1;
+        }
+        Ok(())
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[..2]).join(&hash[2..])
+    }
+
+    fn link_file(
+        &self,
+        path: &Path,
+        seen: &mut HashSet<String>,
+        stats: &mut ObjectStats,
+    ) -> Result<(), RefstoreError> {
+        let hash = hash_file(path)?;
+        let object = self.object_path(&hash);
+        stats.files += 1;
+
+        if object.exists() {
+            if !same_file(path, &object) {
+                // Link under a temporary name and rename over the original so
+                // the path is never missing.
+                let tmp = path.with_file_name(format!(
+                    ".{}.refstore-link",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ));
+                let _ = fs::remove_file(&tmp);
+                fs::hard_link(&object, &tmp)
+                    .and_then(|()| fs::rename(&tmp, path))
+                    .map_err(|source| RefstoreError::FileWrite {
+                        path: path.to_path_buf(),
+                        source,
+                    })?;
+                stats.deduplicated += 1;
+                stats.bytes_saved += fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            }
+        } else {
+            let parent = object.parent().unwrap_or(&self.root);
+            fs::create_dir_all(parent).map_err(|source| RefstoreError::DirCreate {
+                path: parent.to_path_buf(),
+                source,
+            })?;
+            fs::hard_link(path, &object).map_err(|source| RefstoreError::FileWrite {
+                path: object.clone(),
+                source,
+            })?;
+        }
+
+        seen.insert(hash);
+        Ok(())
+    }
+}
+
+fn hash_file(path: &Path) -> Result<String, RefstoreError> {
+    let read_err = |source| RefstoreError::FileRead {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut file = fs::File::open(path).map_err(read_err)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).map_err(read_err)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Reassemble the hash from `objects/<aa>/<rest>`.
+fn object_hash(path: &Path) -> String {
+    let prefix = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .unwrap_or_default()
+        .to_string_lossy();
+    let rest = path.file_name().unwrap_or_default().to_string_lossy();
+    format!("{prefix}{rest}")
+}
+
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &Path, _b: &Path) -> bool {
+    // Relinking an existing link is harmless, just slower.
+    false
+}
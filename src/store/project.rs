@@ -3,10 +3,14 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::error::RefstoreError;
-use crate::model::{Manifest, ManifestEntry};
+use crate::model::migrate::{self, Loaded};
+use crate::model::{Manifest, ManifestEntry, ReferenceKind, SyncState, split_qualified};
 use crate::store::RepositoryStore;
 
 const MANIFEST_FILE: &str = "refstore.toml";
+/// Kept inside `.references/` so it is ignored, or committed, along with the
+/// content it describes.
+const SYNC_STATE_FILE: &str = ".refstore-sync.toml";
 
 pub struct ProjectStore {
     root: PathBuf,
@@ -20,9 +24,16 @@ impl ProjectStore {
             .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
 
         let root = find_manifest_root(&start).ok_or(RefstoreError::ManifestNotFound)?;
-        let manifest = load_manifest(&root)?;
+        let loaded = load_manifest(&root)?;
+        let store = Self {
+            root,
+            manifest: loaded.value,
+        };
+        if loaded.migrated_from.is_some() {
+            store.save_manifest()?;
+        }
 
-        Ok(Self { root, manifest })
+        Ok(store)
     }
 
     pub fn init(path: Option<&Path>, gitignore: bool) -> Result<Self, RefstoreError> {
@@ -64,6 +75,40 @@ impl ProjectStore {
         self.root.join(".references")
     }
 
+    /// What pinned references were last synced from. Missing or unreadable
+    /// state just means everything is synced again.
+    pub fn sync_state(&self) -> SyncState {
+        fs::read_to_string(self.references_dir().join(SYNC_STATE_FILE))
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save_sync_state(&self, state: &SyncState) -> Result<(), RefstoreError> {
+        let path = self.references_dir().join(SYNC_STATE_FILE);
+        if state.references.is_empty() {
+            let _ = fs::remove_file(&path);
+            return Ok(());
+        }
+        let content = toml::to_string_pretty(state)?;
+        fs::write(&path, content).map_err(|source| RefstoreError::FileWrite { path, source })
+    }
+
+    /// Where a reference is synced within `.references/`: the manifest's path
+    /// override, `<name>.md` for snippets, or `<name>/` otherwise.
+    pub fn target_path(
+        &self,
+        name: &str,
+        entry: &ManifestEntry,
+        kind: Option<&ReferenceKind>,
+    ) -> PathBuf {
+        match (&entry.path, kind) {
+            (Some(p), _) => self.references_dir().join(p),
+            (None, Some(ReferenceKind::Snippet)) => self.references_dir().join(format!("{name}.md")),
+            (None, _) => self.references_dir().join(name),
+        }
+    }
+
     pub fn add_reference(
         &mut self,
         name: String,
@@ -128,9 +173,11 @@ impl ProjectStore {
                 }
             })?;
             for ref_name in &bundle.references {
-                resolved
-                    .entry(ref_name.clone())
-                    .or_insert_with(ManifestEntry::default);
+                let (registry, bare) = split_qualified(ref_name);
+                resolved.entry(bare.to_string()).or_insert_with(|| ManifestEntry {
+                    registry: registry.map(str::to_string),
+                    ..Default::default()
+                });
             }
         }
 
@@ -162,14 +209,13 @@ fn find_manifest_root(start: &Path) -> Option<PathBuf> {
     }
 }
 
-fn load_manifest(root: &Path) -> Result<Manifest, RefstoreError> {
+fn load_manifest(root: &Path) -> Result<Loaded<Manifest>, RefstoreError> {
     let path = root.join(MANIFEST_FILE);
     let content = fs::read_to_string(&path).map_err(|source| RefstoreError::FileRead {
         path: path.clone(),
         source,
     })?;
-    let manifest: Manifest = toml::from_str(&content)?;
-    Ok(manifest)
+    migrate::load_manifest(&path, &content)
 }
 
 fn append_gitignore(root: &Path) -> Result<(), RefstoreError> {
@@ -0,0 +1,142 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::error::RefstoreError;
+use crate::model::config::format_size;
+use crate::model::{BinaryPolicy, GlobalConfig};
+
+/// How many offending files an error lists before summarizing the rest.
+const MAX_LISTED: usize = 10;
+
+/// Size limits and binary-file handling applied to content before it is stored.
+#[derive(Debug, Clone, Default)]
+pub struct ContentPolicy {
+    pub max_reference_size: Option<u64>,
+    pub max_file_size: Option<u64>,
+    pub binary_files: BinaryPolicy,
+}
+
+/// Binary files a policy check left out or flagged, relative to the checked directory.
+#[derive(Debug, Default)]
+pub struct ContentReport {
+    pub skipped: Vec<PathBuf>,
+    pub binary: Vec<PathBuf>,
+}
+
+impl ContentPolicy {
+    pub fn from_config(config: &GlobalConfig) -> Self {
+        Self {
+            max_reference_size: config.max_reference_size,
+            max_file_size: config.max_file_size,
+            binary_files: config.binary_files,
+        }
+    }
+
+    /// A policy that lets everything through, for `--force`.
+    pub fn unrestricted() -> Self {
+        Self::default()
+    }
+
+    /// Check every file under `dir` (or `dir` itself if it is a file) against
+    /// the limits. Fails with a summary of the offending files, or if skipping
+    /// binary files would leave nothing; otherwise reports which binary files
+    /// to skip or warn about. Skipped files don't count towards the reference size.
+    pub fn check(&self, name: &str, dir: &Path) -> Result<ContentReport, RefstoreError> {
+        let mut report = ContentReport::default();
+        if self.max_reference_size.is_none()
+            && self.max_file_size.is_none()
+            && self.binary_files == BinaryPolicy::Allow
+        {
+            return Ok(report);
+        }
+
+        let mut total = 0u64;
+        let mut kept = 0usize;
+        let mut oversized = Vec::new();
+        for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+            let entry = entry.map_err(|e| RefstoreError::FileRead {
+                path: dir.to_path_buf(),
+                source: e.into(),
+            })?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = match entry.path().strip_prefix(dir) {
+                Ok(r) if !r.as_os_str().is_empty() => r.to_path_buf(),
+                _ => PathBuf::from(entry.file_name()),
+            };
+
+            if self.binary_files != BinaryPolicy::Allow && is_binary(entry.path())? {
+                if self.binary_files == BinaryPolicy::Skip {
+                    report.skipped.push(relative);
+                    continue;
+                }
+                report.binary.push(relative.clone());
+            }
+
+            kept += 1;
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            total += size;
+            if self.max_file_size.is_some_and(|max| size > max) {
+                oversized.push((relative, size));
+            }
+        }
+
+        if kept == 0 && !report.skipped.is_empty() {
+            return Err(RefstoreError::NothingToStore {
+                name: name.to_string(),
+                skipped: report.skipped.len(),
+            });
+        }
+
+        let too_big = self.max_reference_size.filter(|&max| total > max);
+        if oversized.is_empty() && too_big.is_none() {
+            return Ok(report);
+        }
+
+        let mut summary = Vec::new();
+        if let Some(max) = too_big {
+            summary.push(format!(
+                "  total size {} exceeds max_reference_size {}",
+                format_size(total),
+                format_size(max)
+            ));
+        }
+        if let Some(max) = self.max_file_size
+            && !oversized.is_empty()
+        {
+            summary.push(format!(
+                "  {} file(s) exceed max_file_size {}:",
+                oversized.len(),
+                format_size(max)
+            ));
+            oversized.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+            for (path, size) in oversized.iter().take(MAX_LISTED) {
+                summary.push(format!("    {} ({})", path.display(), format_size(*size)));
+            }
+            if oversized.len() > MAX_LISTED {
+                summary.push(format!("    ... and {} more", oversized.len() - MAX_LISTED));
+            }
+        }
+        Err(RefstoreError::QuotaExceeded {
+            name: name.to_string(),
+            summary: summary.join("\n"),
+        })
+    }
+}
+
+/// Same heuristic as git: a NUL byte in the first 8000 bytes.
+fn is_binary(path: &Path) -> Result<bool, RefstoreError> {
+    let read_err = |source| RefstoreError::FileRead {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut buf = Vec::with_capacity(8000);
+    fs::File::open(path)
+        .map_err(read_err)?
+        .take(8000)
+        .read_to_end(&mut buf)
+        .map_err(read_err)?;
+    Ok(buf.contains(&0))
+}
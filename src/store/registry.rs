@@ -2,6 +2,8 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::error::RefstoreError;
+use crate::git::{self, GitBackend};
+use crate::model::migrate::{self, Loaded};
 use crate::model::{Bundle, Reference, RepositoryIndex};
 
 /// A registry is a directory containing an index.toml and a content/ subdirectory.
@@ -9,20 +11,32 @@ use crate::model::{Bundle, Reference, RepositoryIndex};
 pub struct RegistryStore {
     root: PathBuf,
     index: RepositoryIndex,
+    /// Set when `index.toml` was written in an older format and upgraded on load.
+    migrated_from: Option<u32>,
 }
 
 impl RegistryStore {
     /// Open a registry from a directory.
     pub fn open(root: &Path) -> Result<Self, RefstoreError> {
-        let index = load_registry_index(root)?;
+        let loaded = load_registry_index(root)?;
         Ok(Self {
             root: root.to_path_buf(),
-            index,
+            index: loaded.value,
+            migrated_from: loaded.migrated_from,
         })
     }
 
+    /// A registry whose index was already loaded, e.g. from the index cache.
+    pub fn from_index(root: PathBuf, index: RepositoryIndex, migrated_from: Option<u32>) -> Self {
+        Self {
+            root,
+            index,
+            migrated_from,
+        }
+    }
+
     /// Create a new empty registry at the given path.
-    pub fn init_new(path: &Path) -> Result<(), RefstoreError> {
+    pub fn init_new(path: &Path, git: &dyn GitBackend) -> Result<(), RefstoreError> {
         fs::create_dir_all(path).map_err(|source| RefstoreError::DirCreate {
             path: path.to_path_buf(),
             source,
@@ -38,19 +52,37 @@ impl RegistryStore {
         fs::write(&index_path, content)
             .map_err(|source| RefstoreError::FileWrite { path: index_path, source })?;
 
-        crate::git::init(path)?;
-        crate::git::ensure_gitignore(path, &["config.toml"])?;
-        crate::git::commit(path, &["."], "Initialize registry")?;
+        git.init(path)?;
+        git::ensure_gitignore(path, &["config.toml"])?;
+        git.commit(path, &["."], "Initialize registry")?;
 
         Ok(())
     }
 
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
     pub fn content_path(&self, name: &str) -> PathBuf {
         self.root.join("content").join(name)
     }
 
+    /// The single Markdown file holding a snippet's text.
+    pub fn snippet_path(&self, name: &str) -> PathBuf {
+        self.content_path(name).join(format!("{name}.md"))
+    }
+
+    /// The format version `index.toml` was upgraded from, if it was.
+    pub fn migrated_from(&self) -> Option<u32> {
+        self.migrated_from
+    }
+
     // --- Read operations ---
 
+    pub fn index(&self) -> &RepositoryIndex {
+        &self.index
+    }
+
     pub fn get(&self, name: &str) -> Option<&Reference> {
         self.index.references.get(name)
     }
@@ -60,15 +92,15 @@ impl RegistryStore {
             .references
             .values()
             .filter(|r| {
-                if let Some(t) = tag {
-                    if !r.tags.iter().any(|rt| rt == t) {
-                        return false;
-                    }
+                if let Some(t) = tag
+                    && !r.tags.iter().any(|rt| rt == t)
+                {
+                    return false;
                 }
-                if let Some(k) = kind {
-                    if r.kind.to_string() != k {
-                        return false;
-                    }
+                if let Some(k) = kind
+                    && r.kind.to_string() != k
+                {
+                    return false;
                 }
                 true
             })
@@ -108,16 +140,18 @@ impl RegistryStore {
 }
 
 /// Load a registry index from a directory.
-fn load_registry_index(root: &Path) -> Result<RepositoryIndex, RefstoreError> {
+fn load_registry_index(root: &Path) -> Result<Loaded<RepositoryIndex>, RefstoreError> {
     let path = root.join("index.toml");
     if !path.exists() {
-        return Ok(RepositoryIndex::default());
+        return Ok(Loaded {
+            value: RepositoryIndex::default(),
+            migrated_from: None,
+        });
     }
     let content = fs::read_to_string(&path).map_err(|source| RefstoreError::FileRead {
         path: path.clone(),
         source,
     })?;
 
-    let index: RepositoryIndex = toml::from_str(&content)?;
-    Ok(index)
+    migrate::load_index(&path, &content)
 }
@@ -0,0 +1,57 @@
+//! Per-reference releases, recorded as `<name>/v<semver>` tags on the data dir
+//! so that each reference is versioned independently of registry-wide tags.
+
+use semver::{Version, VersionReq};
+
+/// A released version of one reference.
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub version: Version,
+    /// Abbreviated hash of the tagged commit
+    pub commit: String,
+    pub date: String,
+}
+
+/// A semver-shaped version of a reference: one of its releases or a
+/// registry-wide tag like `v1.2.0`.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub version: Version,
+    pub git_ref: String,
+}
+
+/// What a pinned version resolved to.
+#[derive(Debug, Clone)]
+pub struct ResolvedVersion {
+    /// Full hash of the commit the pin resolved to
+    pub commit: String,
+    /// Set when the pin resolved to a semver candidate
+    pub version: Option<Version>,
+}
+
+/// Tag recording `version` of the reference `name`.
+pub fn tag_name(name: &str, version: &Version) -> String {
+    format!("{name}/v{version}")
+}
+
+/// Parse a version as written by users, with or without a leading `v`.
+pub fn parse_version(s: &str) -> Result<Version, semver::Error> {
+    Version::parse(s.strip_prefix('v').unwrap_or(s))
+}
+
+/// Split a release tag into its reference name and version.
+pub fn parse_tag(tag: &str) -> Option<(&str, Version)> {
+    let (name, version) = tag.rsplit_once("/v")?;
+    Some((name, Version::parse(version).ok()?))
+}
+
+/// Whether a pin is a version requirement (`^1.2`, `~2.0`, `>=1, <2`, `1.*`)
+/// rather than an exact version or git ref.
+pub fn is_range(pin: &str) -> bool {
+    pin.starts_with(['^', '~', '>', '<', '=']) || pin.contains(['*', ','])
+}
+
+/// The highest candidate matching `req`. `candidates` must be sorted newest first.
+pub fn highest_match<'a>(candidates: &'a [Candidate], req: &VersionReq) -> Option<&'a Candidate> {
+    candidates.iter().find(|c| req.matches(&c.version))
+}
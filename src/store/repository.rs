@@ -1,15 +1,30 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
 
 use chrono::Utc;
+use sha2::{Digest, Sha256};
 
 use crate::error::RefstoreError;
-use crate::git;
+use crate::extract;
+use crate::git::{self, GitBackend};
+use crate::model::migrate::INDEX_VERSION;
 use crate::model::{
-    Bundle, GlobalConfig, Reference, ReferenceSource, Registry,
+    BuildStep, Bundle, GitRefKind, GlobalConfig, Layer, Reference, ReferenceKind, ReferenceSource, Registry,
+    split_qualified,
 };
+use crate::plugin;
+use crate::secrets::{Finding, Scanner};
 
+use super::history::HistoryEntry;
+use super::index_cache::{self, MergedIndex};
+use super::objects::{ObjectStats, ObjectStore};
+use super::quota::{ContentPolicy, ContentReport};
 use super::registry::RegistryStore;
+use super::release::{self, Candidate, Release, ResolvedVersion};
 
 /// Information about where a reference was resolved from.
 pub struct ResolvedReference<'a> {
@@ -18,11 +33,107 @@ pub struct ResolvedReference<'a> {
     pub registry_name: &'a str,
 }
 
+/// Safety checks on fetched content that the caller may waive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContentChecks {
+    /// Commit even if the secret scan finds likely credentials
+    pub allow_secrets: bool,
+    /// Ignore the configured size limits and binary-file policy
+    pub force: bool,
+}
+
+/// What changed in a reference's content during `update`.
+pub struct UpdateSummary {
+    pub changes: Vec<git::FileChange>,
+    /// Binary files the content policy skipped or flagged
+    pub content: ContentReport,
+    /// Upstream version before and after the update: the commit for git
+    /// sources, or whatever a source helper reported.
+    pub upstream: Option<(Option<String>, String)>,
+}
+
+impl UpdateSummary {
+    /// Counts of changed files, e.g. "1 added, 2 modified".
+    pub fn short_stat(&self) -> String {
+        let count = |kind| self.changes.iter().filter(|c| c.kind == kind).count();
+        let parts: Vec<String> = [
+            (count(git::ChangeKind::Added), "added"),
+            (count(git::ChangeKind::Modified), "modified"),
+            (count(git::ChangeKind::Removed), "removed"),
+        ]
+        .into_iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, label)| format!("{n} {label}"))
+        .collect();
+
+        if parts.is_empty() {
+            "no changes".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+
+    /// Upstream range, e.g. "1a2b3c4d..5e6f7a8b" (commit hashes are abbreviated).
+    pub fn upstream_range(&self) -> Option<String> {
+        let (old, new) = self.upstream.as_ref()?;
+        let (new, old) = (abbreviate(new), old.as_deref().map(abbreviate));
+        Some(match old {
+            Some(old) if old == new => format!("{new} (unchanged)"),
+            Some(old) => format!("{old}..{new}"),
+            None => new.to_string(),
+        })
+    }
+
+    /// One line per changed file, e.g. "M docs/guide.md (+3 -1)".
+    pub fn file_lines(&self) -> Vec<String> {
+        self.changes
+            .iter()
+            .map(|c| {
+                let marker = match c.kind {
+                    git::ChangeKind::Added => "A",
+                    git::ChangeKind::Removed => "D",
+                    git::ChangeKind::Modified => "M",
+                };
+                let lines = match (c.lines_added, c.lines_removed) {
+                    (Some(a), Some(r)) => format!(" (+{a} -{r})"),
+                    _ => " (binary)".to_string(),
+                };
+                format!("{marker} {}{lines}", c.path)
+            })
+            .collect()
+    }
+
+    fn commit_message(&self, name: &str) -> String {
+        let mut message = format!("Update reference: {name} ({})", self.short_stat());
+        let mut body = Vec::new();
+        if let Some(range) = self.upstream_range() {
+            body.push(format!("upstream: {range}"));
+        }
+        body.extend(self.file_lines());
+        if !body.is_empty() {
+            message.push_str("\n\n");
+            message.push_str(&body.join("\n"));
+        }
+        message
+    }
+}
+
+/// What `undo` restores: the local registry, not registries or config.
+const STORE_PATHS: [&str; 2] = ["index.toml", "content"];
+
 pub struct RepositoryStore {
     root: PathBuf,
     local: RegistryStore,
+    /// Read-only stores searched after `local` and before `remotes`.
+    layers: Vec<(String, PathBuf, RegistryStore)>,
     remotes: Vec<(String, RegistryStore)>,
     config: GlobalConfig,
+    git: Box<dyn GitBackend>,
+    /// Lookup tables across all registries, built on first use unless they
+    /// came from the index cache.
+    merged: OnceLock<MergedIndex>,
+    /// Git mirrors already fetched by this process.
+    fetched_mirrors: Mutex<HashSet<PathBuf>>,
 }
 
 impl RepositoryStore {
@@ -31,7 +142,27 @@ impl RepositoryStore {
             Some(dir) => dir.to_path_buf(),
             None => default_data_dir()?,
         };
+        Self::open_with(root, None)
+    }
+
+    /// Open the data dir for reading only: nothing is created, initialized,
+    /// committed or migrated on disk, and no git command runs. Suits commands
+    /// that only look things up, and data dirs on read-only mounts. A missing
+    /// data dir opens as an empty store.
+    pub fn open_read_only(data_dir: Option<&Path>) -> Result<Self, RefstoreError> {
+        let root = match data_dir {
+            Some(dir) => dir.to_path_buf(),
+            None => default_data_dir()?,
+        };
+        let config = load_config(&root)?;
+        let git = git::select(config.git_backend)?;
+        let (store, _) = Self::load(root, config, git)?;
+        Ok(store)
+    }
 
+    /// Open the data dir at `root`, running git operations through `git`
+    /// instead of the configured backend when given.
+    pub(crate) fn open_with(root: PathBuf, git: Option<Box<dyn GitBackend>>) -> Result<Self, RefstoreError> {
         fs::create_dir_all(&root).map_err(|source| RefstoreError::DirCreate {
             path: root.clone(),
             source,
@@ -42,32 +173,100 @@ impl RepositoryStore {
         })?;
 
         let config = load_config(&root)?;
-        let local = RegistryStore::open(&root)?;
+        let git = match git {
+            Some(git) => git,
+            None => git::select(config.git_backend)?,
+        };
 
         // Ensure the data dir is a git repo
-        git::init(&root)?;
-        git::ensure_gitignore(&root, &["config.toml"])?;
+        git.init(&root)?;
+        git::ensure_gitignore(&root, &["config.toml", "objects/", "cache/"])?;
 
         // If this is a fresh init (no commits yet), do an initial commit
-        if git::head_hash(&root).is_err() {
-            git::commit(&root, &["."], "Initialize refstore repository")?;
+        if git.head(&root).is_err() {
+            git.commit(&root, &["."], "Initialize refstore repository")?;
+        }
+
+        let (mut store, cached) = Self::load(root, config, git)?;
+
+        // Persist an index upgraded from an older format so it only migrates once
+        if let Some(from) = store.local.migrated_from() {
+            store.local.save_index()?;
+            store.git.commit(
+                &store.root,
+                &["index.toml"],
+                &format!("Migrate index from format version {from} to {INDEX_VERSION}"),
+            )?;
+            store.local = RegistryStore::open(&store.root)?;
+            store.reindex();
+        } else if !cached {
+            store.reindex();
         }
 
-        // Load remote registries from submodules
-        let remotes = load_remote_registries(&root);
+        Ok(store)
+    }
+
+    /// Load the local registry, layers and remote registries, from the index
+    /// cache if it is up to date. Also returns whether the cache was used.
+    fn load(root: PathBuf, config: GlobalConfig, git: Box<dyn GitBackend>) -> Result<(Self, bool), RefstoreError> {
+        let remote_dirs = remote_registry_dirs(&root, &config);
+        let layer_dirs = layer_dirs(&config, &remote_dirs)?;
+        let ahead = remote_dirs.partition_point(|(name, _)| config.registry_priority(name) > 0);
+        let sources: Vec<(String, PathBuf)> = remote_dirs[..ahead]
+            .iter()
+            .cloned()
+            .chain(std::iter::once(("local".to_string(), root.clone())))
+            .chain(layer_dirs.iter().cloned())
+            .chain(remote_dirs[ahead..].iter().cloned())
+            .collect();
+
+        let merged = OnceLock::new();
+        let (local, layers, remotes, cached) = match index_cache::load(&root, &sources) {
+            Some((stores, index)) => {
+                let _ = merged.set(index);
+                let mut stores = stores.into_iter();
+                let mut remote_stores: Vec<_> = stores.by_ref().take(ahead).collect();
+                let local = stores.next().expect("the cache covers the local registry");
+                let layers = layer_dirs
+                    .into_iter()
+                    .zip(stores.by_ref())
+                    .map(|((name, path), store)| (name, path, store))
+                    .collect();
+                remote_stores.extend(stores);
+                let remotes = remote_dirs.into_iter().map(|(name, _)| name).zip(remote_stores).collect();
+                (local, layers, remotes, true)
+            }
+            None => {
+                let local = RegistryStore::open(&root)?;
+                let layers = layer_dirs
+                    .into_iter()
+                    .map(|(name, path)| RegistryStore::open(&path).map(|store| (name, path, store)))
+                    .collect::<Result<_, _>>()?;
+                (local, layers, load_remote_registries(&root, &config)?, false)
+            }
+        };
 
-        Ok(Self {
+        let store = Self {
             root,
             local,
+            layers,
             remotes,
             config,
-        })
+            git,
+            merged,
+            fetched_mirrors: Mutex::new(HashSet::new()),
+        };
+        Ok((store, cached))
     }
 
     pub fn root(&self) -> &Path {
         &self.root
     }
 
+    pub fn git(&self) -> &dyn GitBackend {
+        &*self.git
+    }
+
     pub fn config(&self) -> &GlobalConfig {
         &self.config
     }
@@ -89,29 +288,81 @@ impl RepositoryStore {
 
     // --- Multi-registry resolution ---
 
-    /// Resolve a reference by name across all registries.
-    /// Local registry is searched first, then remote registries.
-    pub fn resolve(&self, name: &str) -> Option<ResolvedReference<'_>> {
-        if let Some(r) = self.local.get(name) {
-            return Some(ResolvedReference {
-                reference: r,
-                content_path: self.local.content_path(name),
-                registry_name: "local",
-            });
-        }
-        for (reg_name, store) in &self.remotes {
-            if let Some(r) = store.get(name) {
-                return Some(ResolvedReference {
-                    reference: r,
-                    content_path: store.content_path(name),
-                    registry_name: reg_name,
-                });
-            }
+    /// All stores in resolution order: remote registries with a positive
+    /// priority, then local, then layers, then the remaining remotes.
+    fn stores(&self) -> impl Iterator<Item = (&str, &RegistryStore)> {
+        let (ahead, behind) = self.remotes.split_at(self.remotes_ahead());
+        ahead
+            .iter()
+            .map(|(n, s)| (n.as_str(), s))
+            .chain(std::iter::once(("local", &self.local)))
+            .chain(self.layers.iter().map(|(n, _, s)| (n.as_str(), s)))
+            .chain(behind.iter().map(|(n, s)| (n.as_str(), s)))
+    }
+
+    /// Names of all registries and layers in resolution order.
+    pub fn resolution_order(&self) -> Vec<&str> {
+        self.stores().map(|(name, _)| name).collect()
+    }
+
+    /// How many remotes resolve before the local registry.
+    fn remotes_ahead(&self) -> usize {
+        self.remotes
+            .partition_point(|(name, _)| self.config.registry_priority(name) > 0)
+    }
+
+    /// Keep `remotes` in resolution order after a registry or priority changed.
+    fn sort_remotes(&mut self) {
+        let config = &self.config;
+        self.remotes
+            .sort_by(|(a, _), (b, _)| resolution_key(config, a).cmp(&resolution_key(config, b)));
+    }
+
+    fn merged(&self) -> &MergedIndex {
+        self.merged
+            .get_or_init(|| MergedIndex::build(self.stores().map(|(_, store)| store)))
+    }
+
+    /// Rebuild the merged index after a registry changed, and refresh the
+    /// index cache for the next command.
+    fn reindex(&mut self) {
+        self.merged = OnceLock::new();
+        let stores: Vec<_> = self.stores().collect();
+        // Only a cache: failing to write it costs the next command a re-parse
+        let _ = index_cache::save(&self.root, &stores, self.merged());
+    }
+
+    /// Save the local registry's index after a change to it.
+    fn save_local_index(&mut self) -> Result<(), RefstoreError> {
+        self.local.save_index()?;
+        self.reindex();
+        Ok(())
+    }
+
+    /// The registry holding `name`: the one it is qualified with
+    /// (`team/react-docs`), or else the first in resolution order.
+    fn owner(&self, name: &str) -> Option<(&str, &RegistryStore)> {
+        match split_qualified(name) {
+            (Some(registry), bare) => self
+                .stores()
+                .find(|(reg_name, store)| *reg_name == registry && store.get(bare).is_some()),
+            (None, _) => self.stores().nth(self.merged().winner(name)?),
         }
-        None
     }
 
-    /// Get a reference by name (searches all registries, local first).
+    /// Resolve a reference by name across all registries, in resolution order.
+    /// A registry-qualified name (`team/react-docs`) only looks in that registry.
+    pub fn resolve(&self, name: &str) -> Option<ResolvedReference<'_>> {
+        let (reg_name, store) = self.owner(name)?;
+        let name = split_qualified(name).1;
+        store.get(name).map(|r| ResolvedReference {
+            reference: r,
+            content_path: store.content_path(name),
+            registry_name: reg_name,
+        })
+    }
+
+    /// Get a reference by name (searches all registries in resolution order).
     pub fn get(&self, name: &str) -> Option<&Reference> {
         self.resolve(name).map(|r| r.reference)
     }
@@ -122,45 +373,46 @@ impl RepositoryStore {
     }
 
     /// List all references across all registries.
-    /// Local references take precedence (dedup by name).
+    /// Earlier stores take precedence (dedup by name).
     pub fn list(&self, tag: Option<&str>, kind: Option<&str>) -> Vec<ResolvedReference<'_>> {
-        let mut seen = std::collections::BTreeSet::new();
-        let mut result = Vec::new();
-
-        // Local first
-        for r in self.local.list(tag, kind) {
-            seen.insert(r.name.clone());
-            result.push(ResolvedReference {
-                reference: r,
-                content_path: self.local.content_path(&r.name),
-                registry_name: "local",
-            });
-        }
+        self.merged()
+            .names(tag)
+            .iter()
+            .filter_map(|name| self.resolve(name))
+            .filter(|r| kind.is_none_or(|k| r.reference.kind.to_string() == k))
+            .collect()
+    }
 
-        // Then remotes
-        for (reg_name, store) in &self.remotes {
-            for r in store.list(tag, kind) {
-                if seen.insert(r.name.clone()) {
-                    result.push(ResolvedReference {
-                        reference: r,
-                        content_path: store.content_path(&r.name),
-                        registry_name: reg_name,
-                    });
-                }
-            }
-        }
+    /// Whether any stores besides the local registry (layers or remotes) are configured.
+    pub fn has_remotes(&self) -> bool {
+        !self.remotes.is_empty() || !self.layers.is_empty()
+    }
 
-        result
+    /// The directory of a layer, if `name` refers to one.
+    pub fn layer_path(&self, name: &str) -> Option<&Path> {
+        self.layers
+            .iter()
+            .find(|(n, _, _)| n == name)
+            .map(|(_, p, _)| p.as_path())
     }
 
-    /// Whether any remote registries are configured.
-    pub fn has_remotes(&self) -> bool {
-        !self.remotes.is_empty()
+    /// List layers in precedence order as (name, path, store).
+    pub fn list_layers(&self) -> Vec<(&str, &Path, &RegistryStore)> {
+        self.layers
+            .iter()
+            .map(|(n, p, s)| (n.as_str(), p.as_path(), s))
+            .collect()
     }
 
     // --- Local registry write operations ---
 
-    pub fn add(&mut self, reference: Reference) -> Result<(), RefstoreError> {
+    /// Fetch a new reference and commit it. Unless waived by `checks`, the
+    /// fetched content must pass the size limits and the secret scan first.
+    pub fn add(
+        &mut self,
+        mut reference: Reference,
+        checks: ContentChecks,
+    ) -> Result<ContentReport, RefstoreError> {
         if self.local.get(&reference.name).is_some() {
             return Err(RefstoreError::ReferenceExists {
                 name: reference.name,
@@ -169,19 +421,118 @@ impl RepositoryStore {
 
         validate_name(&reference.name)?;
 
+        resolve_ref_kind(&reference.name, &mut reference.source)?;
+
         let content_dir = self.local.content_path(&reference.name);
-        self.fetch_content(&reference, &content_dir)?;
+        // Don't leave a partial checkout behind to block the next attempt
+        let (fetched, report) = self
+            .fetch_content(&reference, &content_dir, &self.content_policy(checks))
+            .and_then(|fetched| {
+                if !checks.allow_secrets {
+                    check_secrets(&self.root, &reference.name, &content_dir)?;
+                }
+                Ok(fetched)
+            })
+            .inspect_err(|_| {
+                let _ = fs::remove_dir_all(&content_dir);
+            })?;
+        if let Some(rev) = fetched {
+            reference.checksum = Some(rev);
+        }
 
         let name = reference.name.clone();
         self.local.index_mut().references.insert(reference.name.clone(), reference);
-        self.local.save_index()?;
+        self.save_local_index()?;
+
+        let content_rel = format!("content/{name}");
+        self.git.commit(&self.root, &[&content_rel, "index.toml"], &format!("Add reference: {name}"))?;
+
+        Ok(report)
+    }
+
+    /// Add a snippet reference whose content is `text`, stored as `content/<name>/<name>.md`.
+    /// Like fetched content, the text is scanned for credentials unless `allow_secrets`.
+    pub fn add_snippet(
+        &mut self,
+        reference: Reference,
+        text: &str,
+        allow_secrets: bool,
+    ) -> Result<(), RefstoreError> {
+        if self.local.get(&reference.name).is_some() {
+            return Err(RefstoreError::ReferenceExists {
+                name: reference.name,
+            });
+        }
+
+        validate_name(&reference.name)?;
+        if !allow_secrets {
+            check_snippet_secrets(&self.root, &reference.name, text)?;
+        }
+
+        let name = reference.name.clone();
+        self.write_snippet(&name, text)?;
+        self.local.index_mut().references.insert(reference.name.clone(), reference);
+        self.save_local_index()?;
+
+        let content_rel = format!("content/{name}");
+        self.git.commit(&self.root, &[&content_rel, "index.toml"], &format!("Add reference: {name}"))?;
+
+        Ok(())
+    }
+
+    /// Replace the text of a snippet in the local registry.
+    pub fn edit_snippet(&mut self, name: &str, text: &str, allow_secrets: bool) -> Result<(), RefstoreError> {
+        let reference = self.local.get(name).ok_or_else(|| RefstoreError::ReferenceNotFound {
+            name: name.to_string(),
+        })?;
+        if reference.kind != ReferenceKind::Snippet {
+            return Err(RefstoreError::NotASnippet {
+                name: name.to_string(),
+            });
+        }
+        if !allow_secrets {
+            check_snippet_secrets(&self.root, name, text)?;
+        }
+
+        self.write_snippet(name, text)?;
+        if let Some(r) = self.local.index_mut().references.get_mut(name) {
+            r.last_synced = Some(Utc::now());
+        }
+        self.save_local_index()?;
 
         let content_rel = format!("content/{name}");
-        git::commit(&self.root, &[&content_rel, "index.toml"], &format!("Add reference: {name}"))?;
+        self.git.commit(&self.root, &[&content_rel, "index.toml"], &format!("Update reference: {name}"))?;
 
         Ok(())
     }
 
+    /// Read the current text of a snippet in the local registry.
+    pub fn snippet_text(&self, name: &str) -> Result<String, RefstoreError> {
+        let reference = self.local.get(name).ok_or_else(|| RefstoreError::ReferenceNotFound {
+            name: name.to_string(),
+        })?;
+        if reference.kind != ReferenceKind::Snippet {
+            return Err(RefstoreError::NotASnippet {
+                name: name.to_string(),
+            });
+        }
+
+        let path = self.local.snippet_path(name);
+        fs::read_to_string(&path).map_err(|source| RefstoreError::FileRead { path, source })
+    }
+
+    fn write_snippet(&self, name: &str, text: &str) -> Result<(), RefstoreError> {
+        let content_dir = self.local.content_path(name);
+        fs::create_dir_all(&content_dir).map_err(|source| RefstoreError::DirCreate {
+            path: content_dir.clone(),
+            source,
+        })?;
+        let path = self.local.snippet_path(name);
+        // Replace rather than overwrite: the file may be hardlinked into objects/
+        let _ = fs::remove_file(&path);
+        fs::write(&path, text).map_err(|source| RefstoreError::FileWrite { path, source })
+    }
+
     pub fn remove(&mut self, name: &str) -> Result<Reference, RefstoreError> {
         let reference = self
             .local
@@ -197,16 +548,18 @@ impl RepositoryStore {
             let _ = fs::remove_dir_all(&content_dir);
         }
 
-        self.local.save_index()?;
+        self.save_local_index()?;
 
         let content_rel = format!("content/{name}");
-        git::commit_removals(&self.root, &[&content_rel, "index.toml"], &format!("Remove reference: {name}"))?;
+        self.git.commit(&self.root, &[&content_rel, "index.toml"], &format!("Remove reference: {name}"))?;
 
         Ok(reference)
     }
 
-    pub fn update(&mut self, name: &str) -> Result<(), RefstoreError> {
-        let reference = self
+    /// Re-fetch a reference from its source and commit the result, returning
+    /// a file-level summary of what changed since the previous commit.
+    pub fn update(&mut self, name: &str, checks: ContentChecks) -> Result<UpdateSummary, RefstoreError> {
+        let mut reference = self
             .local
             .get(name)
             .ok_or_else(|| RefstoreError::ReferenceNotFound {
@@ -214,53 +567,76 @@ impl RepositoryStore {
             })?
             .clone();
 
+        // Check before wiping content: a snippet's content *is* its source
+        if reference.source == ReferenceSource::Inline {
+            return Err(inline_not_fetchable(name));
+        }
+
+        // References added before ref kinds were recorded get classified on their next update
+        resolve_ref_kind(&reference.name, &mut reference.source)?;
+
         let content_dir = self.local.content_path(name);
         if content_dir.exists() {
             let _ = fs::remove_dir_all(&content_dir);
         }
 
-        self.fetch_content(&reference, &content_dir)?;
+        let content_rel = format!("content/{name}");
+        // On failure, put the previously committed content back
+        let (new_rev, content) = self
+            .fetch_content(&reference, &content_dir, &self.content_policy(checks))
+            .and_then(|fetched| {
+                if !checks.allow_secrets {
+                    check_secrets(&self.root, name, &content_dir)?;
+                }
+                Ok(fetched)
+            })
+            .inspect_err(|_| {
+                let _ = fs::remove_dir_all(&content_dir);
+                let _ = self.git.restore(&self.root, "HEAD", &[&content_rel]);
+            })?;
 
         if let Some(r) = self.local.index_mut().references.get_mut(name) {
+            r.source = reference.source.clone();
             r.last_synced = Some(Utc::now());
-            if let ReferenceSource::Git { .. } = &r.source {
-                if let Ok(hash) = git::head_hash(&content_dir) {
-                    r.checksum = Some(hash);
-                }
+            if new_rev.is_some() {
+                r.checksum = new_rev.clone();
             }
         }
-        self.local.save_index()?;
+        self.save_local_index()?;
 
-        let content_rel = format!("content/{name}");
-        git::commit_removals(&self.root, &[&content_rel, "index.toml"], &format!("Update reference: {name}"))?;
+        let summary = UpdateSummary {
+            changes: self.git.pending_changes(&self.root, &content_rel)?,
+            content,
+            upstream: new_rev.map(|new| (reference.checksum.clone(), new)),
+        };
 
-        Ok(())
+        self.git.commit(
+            &self.root,
+            &[&content_rel, "index.toml"],
+            &summary.commit_message(name),
+        )?;
+
+        Ok(summary)
     }
 
     // --- Bundle operations ---
 
     pub fn get_bundle(&self, name: &str) -> Option<&Bundle> {
-        // Search local first, then remotes
-        if let Some(b) = self.local.get_bundle(name) {
-            return Some(b);
-        }
-        for (_, store) in &self.remotes {
-            if let Some(b) = store.get_bundle(name) {
-                return Some(b);
-            }
+        match split_qualified(name) {
+            (Some(registry), bare) => self
+                .stores()
+                .find(|(reg_name, _)| *reg_name == registry)
+                .and_then(|(_, store)| store.get_bundle(bare)),
+            // First match in resolution order
+            (None, _) => self.stores().find_map(|(_, store)| store.get_bundle(name)),
         }
-        None
     }
 
     pub fn list_bundles(&self, tag: Option<&str>) -> Vec<&Bundle> {
         let mut seen = std::collections::BTreeSet::new();
         let mut result = Vec::new();
 
-        for b in self.local.list_bundles(tag) {
-            seen.insert(b.name.clone());
-            result.push(b);
-        }
-        for (_, store) in &self.remotes {
+        for (_, store) in self.stores() {
             for b in store.list_bundles(tag) {
                 if seen.insert(b.name.clone()) {
                     result.push(b);
@@ -290,9 +666,9 @@ impl RepositoryStore {
 
         let name = bundle.name.clone();
         self.local.index_mut().bundles.insert(bundle.name.clone(), bundle);
-        self.local.save_index()?;
+        self.save_local_index()?;
 
-        git::commit(&self.root, &["index.toml"], &format!("Add bundle: {name}"))?;
+        self.git.commit(&self.root, &["index.toml"], &format!("Add bundle: {name}"))?;
 
         Ok(())
     }
@@ -306,9 +682,9 @@ impl RepositoryStore {
             .ok_or_else(|| RefstoreError::BundleNotFound {
                 name: name.to_string(),
             })?;
-        self.local.save_index()?;
+        self.save_local_index()?;
 
-        git::commit(&self.root, &["index.toml"], &format!("Remove bundle: {name}"))?;
+        self.git.commit(&self.root, &["index.toml"], &format!("Remove bundle: {name}"))?;
 
         Ok(bundle)
     }
@@ -349,17 +725,18 @@ impl RepositoryStore {
             bundle.description = Some(desc);
         }
 
-        self.local.save_index()?;
+        self.save_local_index()?;
 
-        git::commit(&self.root, &["index.toml"], &format!("Update bundle: {name}"))?;
+        self.git.commit(&self.root, &["index.toml"], &format!("Update bundle: {name}"))?;
 
         Ok(())
     }
 
     // --- Registry management ---
 
-    /// Add a remote registry as a git submodule.
-    pub fn add_registry(&mut self, name: &str, url: &str) -> Result<(), RefstoreError> {
+    /// Add a remote registry as a submodule under `registries/`, tracking the
+    /// remote's default branch or pinned to `git_ref`.
+    pub fn add_registry(&mut self, name: &str, url: &str, git_ref: Option<&str>) -> Result<(), RefstoreError> {
         validate_name(name)?;
 
         if name == "local" {
@@ -369,6 +746,13 @@ impl RepositoryStore {
             });
         }
 
+        if self.layer_path(name).is_some() {
+            return Err(RefstoreError::InvalidName {
+                name: name.to_string(),
+                reason: "a layer can't share its name with a remote registry".to_string(),
+            });
+        }
+
         let submodule_path = format!("registries/{name}");
         let full_path = self.root.join("registries").join(name);
 
@@ -384,8 +768,16 @@ impl RepositoryStore {
             source,
         })?;
 
-        git::submodule_add(&self.root, url, &submodule_path)?;
-        git::commit(&self.root, &[".gitmodules", &submodule_path], &format!("Add registry: {name}"))?;
+        self.git.submodule_add(&self.root, url, &submodule_path)?;
+        if let Some(rev) = git_ref
+            && let Err(e) = self.git.submodule_pin(&self.root, &submodule_path, rev)
+        {
+            // Don't leave a half-added registry blocking the next attempt
+            let _ = self.git.submodule_remove(&self.root, &submodule_path);
+            return Err(e);
+        }
+        self.git.commit(&self.root, &[".gitmodules", &submodule_path], &format!("Add registry: {name}"))?;
+        self.dedup_registry(name)?;
 
         // Load the new registry
         let store = RegistryStore::open(&full_path)?;
@@ -395,7 +787,11 @@ impl RepositoryStore {
         self.config.registries.push(Registry {
             name: name.to_string(),
             url: url.to_string(),
+            r#ref: git_ref.map(str::to_string),
+            priority: 0,
         });
+        self.sort_remotes();
+        self.reindex();
         self.save_config()?;
 
         Ok(())
@@ -403,6 +799,7 @@ impl RepositoryStore {
 
     /// Remove a remote registry.
     pub fn remove_registry(&mut self, name: &str) -> Result<(), RefstoreError> {
+        validate_name(name)?;
         if !self.root.join("registries").join(name).exists() {
             return Err(RefstoreError::RegistryNotFound {
                 name: name.to_string(),
@@ -410,26 +807,30 @@ impl RepositoryStore {
         }
 
         let submodule_path = format!("registries/{name}");
-        git::submodule_remove(&self.root, &submodule_path)?;
+        self.git.submodule_remove(&self.root, &submodule_path)?;
 
         // git submodule deinit + git rm already stages changes,
         // so just commit directly (also stage .gitmodules which may have changed)
-        git::commit(&self.root, &[".gitmodules"], &format!("Remove registry: {name}"))?;
+        self.git.commit(&self.root, &[".gitmodules"], &format!("Remove registry: {name}"))?;
 
         self.remotes.retain(|(n, _)| n != name);
+        self.reindex();
         self.config.registries.retain(|r| r.name != name);
         self.save_config()?;
 
         Ok(())
     }
 
-    /// Update remote registry/registries (git submodule update --remote).
+    /// Update remote registry/registries: pinned ones are fetched and checked
+    /// out at their pin again, the rest move to their remote's latest commit.
     pub fn update_registry(&mut self, name: Option<&str>) -> Result<(), RefstoreError> {
         match name {
             Some(n) => {
+                validate_name(n)?;
                 let submodule_path = format!("registries/{n}");
-                git::submodule_update(&self.root, Some(&submodule_path))?;
-                git::commit(&self.root, &[&submodule_path], &format!("Update registry: {n}"))?;
+                self.fetch_registry(n)?;
+                self.git.commit(&self.root, &[&submodule_path], &format!("Update registry: {n}"))?;
+                self.dedup_registry(n)?;
 
                 // Reload the registry
                 let full_path = self.root.join("registries").join(n);
@@ -438,17 +839,153 @@ impl RepositoryStore {
                 }
             }
             None => {
-                git::submodule_update(&self.root, None)?;
-                git::commit(&self.root, &["registries"], "Update all registries")?;
+                let mut pinned = false;
+                for (n, _) in &self.remotes {
+                    pinned |= self.registry_pin(n)?.is_some();
+                }
+                if !pinned {
+                    self.git.submodule_update(&self.root, None)?;
+                } else {
+                    let names: Vec<String> = self.remotes.iter().map(|(n, _)| n.clone()).collect();
+                    for n in &names {
+                        self.fetch_registry(n)?;
+                    }
+                }
+                self.git.commit(&self.root, &[".gitmodules", "registries"], "Update all registries")?;
 
                 // Reload all remotes
-                self.remotes = load_remote_registries(&self.root);
+                self.remotes = load_remote_registries(&self.root, &self.config)?;
+                for (n, _) in &self.remotes {
+                    self.dedup_registry(n)?;
+                }
             }
         }
+        self.reindex();
         Ok(())
     }
 
-    /// List remote registries.
+    /// Bring one registry's checkout up to date, honoring its pin.
+    fn fetch_registry(&self, name: &str) -> Result<(), RefstoreError> {
+        let submodule_path = format!("registries/{name}");
+        match self.registry_pin(name)? {
+            Some(rev) => self.git.submodule_pin(&self.root, &submodule_path, &rev),
+            None => self.git.submodule_update(&self.root, Some(&submodule_path)),
+        }
+    }
+
+    /// Pin a remote registry to a tag, branch, or commit and check it out.
+    pub fn pin_registry(&mut self, name: &str, rev: &str) -> Result<(), RefstoreError> {
+        validate_name(name)?;
+        if !self.root.join("registries").join(name).exists() {
+            return Err(RefstoreError::RegistryNotFound {
+                name: name.to_string(),
+            });
+        }
+
+        let submodule_path = format!("registries/{name}");
+        self.git.submodule_pin(&self.root, &submodule_path, rev)?;
+        self.git.commit(
+            &self.root,
+            &[".gitmodules", &submodule_path],
+            &format!("Pin registry: {name} to {rev}"),
+        )?;
+        self.dedup_registry(name)?;
+
+        let full_path = self.root.join("registries").join(name);
+        if let Some((_, store)) = self.remotes.iter_mut().find(|(rn, _)| rn == name) {
+            *store = RegistryStore::open(&full_path)?;
+        }
+        self.reindex();
+
+        self.registry_config_mut(name)?.r#ref = Some(rev.to_string());
+        self.save_config()
+    }
+
+    /// Set the resolution order. `order` names every remote registry, and
+    /// `local` for the local store and its layers, first to last; without
+    /// `local` all remotes come after it. Stored as each remote's priority.
+    pub fn reorder_registries(&mut self, order: &[String]) -> Result<(), RefstoreError> {
+        let mut seen = HashSet::new();
+        for name in order {
+            if name != "local" && !self.remotes.iter().any(|(n, _)| n == name) {
+                return Err(RefstoreError::RegistryNotFound { name: name.clone() });
+            }
+            if !seen.insert(name.as_str()) {
+                return Err(RefstoreError::InvalidRegistryOrder {
+                    reason: format!("'{name}' is listed twice"),
+                });
+            }
+        }
+        if let Some((missing, _)) = self.remotes.iter().find(|(n, _)| !seen.contains(n.as_str())) {
+            return Err(RefstoreError::InvalidRegistryOrder {
+                reason: format!("'{missing}' is missing; list every remote registry"),
+            });
+        }
+
+        let local = order.iter().position(|n| n == "local").unwrap_or(0) as i32;
+        for (position, name) in order.iter().enumerate() {
+            if name != "local" {
+                self.registry_config_mut(name)?.priority = local - position as i32;
+            }
+        }
+        self.sort_remotes();
+        self.reindex();
+        self.save_config()
+    }
+
+    /// The config entry of a remote registry, created from its submodule if
+    /// the registry predates it.
+    fn registry_config_mut(&mut self, name: &str) -> Result<&mut Registry, RefstoreError> {
+        let index = match self.config.registries.iter().position(|r| r.name == name) {
+            Some(index) => index,
+            None => {
+                let submodule_path = format!("registries/{name}");
+                self.config.registries.push(Registry {
+                    name: name.to_string(),
+                    url: self.git.submodule_url(&self.root, &submodule_path)?.unwrap_or_default(),
+                    r#ref: None,
+                    priority: 0,
+                });
+                self.config.registries.len() - 1
+            }
+        };
+        Ok(&mut self.config.registries[index])
+    }
+
+    /// Names defined in more than one registry, each with the registries
+    /// defining it in resolution order. The first registry's copy wins.
+    pub fn shadowed(&self) -> Vec<(&str, Vec<&str>)> {
+        let registries = self.resolution_order();
+        self.merged()
+            .shadowed()
+            .map(|(name, positions)| (name, positions.iter().map(|&p| registries[p]).collect()))
+            .collect()
+    }
+
+    /// The revision a remote registry is pinned to, if any. Read from the
+    /// committed `.gitmodules`, so a cloned data dir keeps its pins, and from
+    /// `config.toml` if that can't be read.
+    pub fn registry_pin(&self, name: &str) -> Result<Option<String>, RefstoreError> {
+        let pinned = self.git.submodule_ref(&self.root, &format!("registries/{name}"))?;
+        Ok(pinned.or_else(|| {
+            self.config
+                .registries
+                .iter()
+                .find(|r| r.name == name)
+                .and_then(|r| r.r#ref.clone())
+        }))
+    }
+
+    /// Tags of a pinned registry that are newer than its checked-out pin, as
+    /// of the last fetch; highest version first.
+    pub fn newer_registry_revs(&self, name: &str) -> Result<Vec<String>, RefstoreError> {
+        match self.registry_pin(name)? {
+            Some(_) => self.git.tags_after(&self.root.join("registries").join(name), "HEAD"),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// List remote registries in resolution order.
     pub fn list_registries(&self) -> Vec<(&str, &RegistryStore)> {
         self.remotes.iter().map(|(n, s)| (n.as_str(), s)).collect()
     }
@@ -459,62 +996,371 @@ impl RepositoryStore {
 
     // --- Versioning ---
 
-    /// Get the version history for a reference (git log of content/<name>/).
-    /// Returns entries from newest to oldest.
+    /// The git repo holding a reference's history: the data dir for local
+    /// references, or the registry's checkout under `registries/` for remote
+    /// ones. `None` for references from a layer that isn't a git repo.
+    fn history_root(&self, name: &str) -> Result<Option<&Path>, RefstoreError> {
+        let (_, store) = self.owner(name).ok_or_else(|| RefstoreError::ReferenceNotFound {
+            name: name.to_string(),
+        })?;
+        // Don't let git fall back to a repo that merely contains the layer
+        Ok(Some(store.root()).filter(|root| self.git.is_repo(root)))
+    }
+
+    /// Get the version history for a reference (git log of content/<name>/ in
+    /// the registry that owns it). Returns entries from newest to oldest.
     pub fn versions(&self, name: &str) -> Result<Vec<git::LogEntry>, RefstoreError> {
-        // Check the reference exists somewhere
-        if self.get(name).is_none() {
-            return Err(RefstoreError::ReferenceNotFound {
+        let Some(root) = self.history_root(name)? else {
+            return Ok(Vec::new());
+        };
+        let content_rel = format!("content/{}", split_qualified(name).1);
+        self.git.log_path(root, &content_rel)
+    }
+
+    /// Content of a reference at a resolved version, extracted once into
+    /// `cache/versions/<registry>/<commit>/<name>` and reused by every later
+    /// sync, from any project. The returned directory must not be modified.
+    pub fn content_at_version(
+        &self,
+        name: &str,
+        resolved: &ResolvedVersion,
+    ) -> Result<PathBuf, RefstoreError> {
+        let resolved_ref = self.resolve(name).ok_or_else(|| RefstoreError::ReferenceNotFound {
+            name: name.to_string(),
+        })?;
+        // resolve_version only succeeds for references with a history root
+        let root = self.history_root(name)?.unwrap_or(&self.root);
+        let name = resolved_ref.reference.name.as_str();
+
+        let cached = self
+            .root
+            .join("cache")
+            .join("versions")
+            .join(resolved_ref.registry_name)
+            .join(&resolved.commit)
+            .join(name);
+        if cached.exists() {
+            return Ok(cached);
+        }
+
+        // Extract under a name private to this process, then move it into
+        // place, so concurrent syncs never see a half-extracted entry
+        let parent = cached.parent().unwrap_or(&self.root);
+        let staging = parent.join(format!(".{name}.extract-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&staging);
+        let content_rel = format!("content/{name}");
+        self.git.archive(root, &resolved.commit, &content_rel, &staging).inspect_err(|_| {
+            let _ = fs::remove_dir_all(&staging);
+        })?;
+
+        if let Err(source) = fs::rename(&staging, &cached) {
+            let _ = fs::remove_dir_all(&staging);
+            // Another process finished the same extraction first
+            if !cached.exists() {
+                return Err(RefstoreError::FileWrite { path: cached, source });
+            }
+        }
+        Ok(cached)
+    }
+
+    /// Map a pinned version to a git ref in the registry that owns `name`. A
+    /// requirement like `^1.2` or `~2.0` picks the highest matching candidate
+    /// (see `version_candidates`). An exact version (`1.2.0` or `v1.2.0`)
+    /// picks that candidate. Anything else is taken as a registry tag or commit.
+    pub fn resolve_version(&self, name: &str, version: &str) -> Result<ResolvedVersion, RefstoreError> {
+        let candidates = self.version_candidates(name)?;
+        let root = self.history_root(name)?;
+        let commit_of = |git_ref: &str| match root {
+            Some(root) => self.git.rev_parse(root, git_ref),
+            None => Err(RefstoreError::VersionNotFound {
                 name: name.to_string(),
+                version: version.to_string(),
+            }),
+        };
+        let found = |c: &Candidate| {
+            Ok(ResolvedVersion {
+                commit: commit_of(&c.git_ref)?,
+                version: Some(c.version.clone()),
+            })
+        };
+
+        if release::is_range(version) {
+            let req = semver::VersionReq::parse(version).map_err(|e| RefstoreError::InvalidVersionReq {
+                version: version.to_string(),
+                reason: e.to_string(),
+            })?;
+            let candidate = release::highest_match(&candidates, &req).ok_or_else(|| {
+                RefstoreError::NoMatchingVersion {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                }
+            })?;
+            return found(candidate);
+        }
+        if let Ok(v) = release::parse_version(version)
+            && let Some(candidate) = candidates.iter().find(|c| c.version == v)
+        {
+            return found(candidate);
+        }
+        if let Some(root) = root
+            && self.git.ref_exists(root, version)
+        {
+            return Ok(ResolvedVersion {
+                commit: commit_of(version)?,
+                version: None,
             });
         }
+        Err(RefstoreError::VersionNotFound {
+            name: name.to_string(),
+            version: version.to_string(),
+        })
+    }
 
-        let content_rel = format!("content/{name}");
-        git::log_path(&self.root, &content_rel)
+    /// Versions a reference can be pinned to, newest first: its releases, plus
+    /// semver-shaped registry tags (`v1.2.0`) for versions it has no release of
+    /// and that include the reference.
+    pub fn version_candidates(&self, name: &str) -> Result<Vec<Candidate>, RefstoreError> {
+        let content_rel = format!("content/{}", split_qualified(name).1);
+        let root = self.history_root(name)?;
+        let mut candidates: Vec<Candidate> = self
+            .releases(name)?
+            .into_iter()
+            .map(|r| Candidate {
+                git_ref: release::tag_name(split_qualified(name).1, &r.version),
+                version: r.version,
+            })
+            .collect();
+        for tag in self.registry_tags(name)? {
+            if let Ok(version) = release::parse_version(&tag)
+                && !candidates.iter().any(|c| c.version == version)
+                && root.is_some_and(|root| self.git.has_path(root, &tag, &content_rel))
+            {
+                candidates.push(Candidate { version, git_ref: tag });
+            }
+        }
+        candidates.sort_by(|a, b| b.version.cmp(&a.version));
+        Ok(candidates)
     }
 
-    /// Extract content for a reference at a specific git ref (tag or commit hash).
-    /// Returns the path to a temporary directory containing the extracted content.
-    /// The caller is responsible for using and cleaning up the returned path.
-    pub fn content_at_version(
+    /// Record the current content of a local reference as `version`, tagged
+    /// `<name>/v<version>`. Returns the tag.
+    pub fn release(
         &self,
         name: &str,
-        version: &str,
-    ) -> Result<PathBuf, RefstoreError> {
-        // Verify the ref exists in the local registry
-        if !git::ref_exists(&self.root, version) {
-            return Err(RefstoreError::SyncFailed {
+        version: &semver::Version,
+        message: Option<&str>,
+    ) -> Result<String, RefstoreError> {
+        if self.local.get(name).is_none() {
+            return Err(RefstoreError::ReferenceNotFound {
                 name: name.to_string(),
-                reason: format!("version '{version}' not found in registry (not a valid tag or commit)"),
             });
         }
 
-        // Create a temp dir for extraction
-        let temp_dir = self.root.join(".tmp-version-extract");
-        if temp_dir.exists() {
-            let _ = fs::remove_dir_all(&temp_dir);
+        let tag = release::tag_name(name, version);
+        if self.git.ref_exists(&self.root, &tag) {
+            return Err(RefstoreError::ReleaseExists {
+                name: name.to_string(),
+                version: version.to_string(),
+            });
         }
 
-        let content_rel = format!("content/{name}");
-        git::archive_path_at_ref(&self.root, version, &content_rel, &temp_dir)?;
+        let message = message.map_or_else(|| format!("Release {name} v{version}"), str::to_string);
+        self.git.create_tag(&self.root, &tag, Some(&message))?;
+        Ok(tag)
+    }
+
+    /// Releases of a reference in the registry that owns it, newest version first.
+    pub fn releases(&self, name: &str) -> Result<Vec<Release>, RefstoreError> {
+        let Some(root) = self.history_root(name)? else {
+            return Ok(Vec::new());
+        };
+        let name = split_qualified(name).1;
+
+        let mut releases: Vec<Release> = self
+            .git
+            .tags(root, &format!("{name}/"))?
+            .into_iter()
+            .filter_map(|tag| {
+                // Other tags under the prefix, like a hand-made `name/draft` or
+                // `name/x/v1.0.0`, aren't releases of this reference
+                let (tag_ref, version) = release::parse_tag(&tag.name)?;
+                (tag_ref == name).then_some(Release {
+                    version,
+                    commit: tag.commit,
+                    date: tag.date,
+                })
+            })
+            .collect();
+        releases.sort_by(|a, b| b.version.cmp(&a.version));
+        Ok(releases)
+    }
+
+    // --- History ---
+
+    /// Recent operations on the data dir, newest first.
+    pub fn history(&self, limit: usize) -> Result<Vec<HistoryEntry>, RefstoreError> {
+        let entries = self.git.log_path(&self.root, ".")?;
+        Ok(entries.into_iter().take(limit).map(HistoryEntry::from_log).collect())
+    }
+
+    /// Restore `index.toml` and `content/` to their state at `to` (default:
+    /// before the last commit that changed them, skipping registry commits)
+    /// and record it as a new commit. Remote registries are left alone.
+    /// Returns the full hash that was restored.
+    pub fn undo(&mut self, to: Option<&str>) -> Result<String, RefstoreError> {
+        let nothing_to_undo = || RefstoreError::NothingToUndo {
+            reason: "no earlier change to the store".to_string(),
+        };
+        let target = match to {
+            Some(rev) => self.git.rev_parse(&self.root, rev)?,
+            None => {
+                let last = self.last_store_commit()?.ok_or_else(nothing_to_undo)?;
+                self.git
+                    .rev_parse(&self.root, &format!("{last}~1"))
+                    .map_err(|_| nothing_to_undo())?
+            }
+        };
+
+        let index_path = self.root.join("index.toml");
+        let index_before = fs::read(&index_path).ok();
+        self.git.restore(&self.root, &target, &STORE_PATHS)?;
+        let changed = fs::read(&index_path).ok() != index_before
+            || !self.git.pending_changes(&self.root, "content")?.is_empty();
+        if !changed {
+            return Err(RefstoreError::NothingToUndo {
+                reason: format!("the store already matches {target:.8}"),
+            });
+        }
+
+        // git doesn't track empty directories
+        let content_dir = self.root.join("content");
+        fs::create_dir_all(&content_dir).map_err(|source| RefstoreError::DirCreate {
+            path: content_dir,
+            source,
+        })?;
+        self.local = RegistryStore::open(&self.root)?;
+        self.reindex();
+
+        self.git.commit(
+            &self.root,
+            &STORE_PATHS,
+            &format!("Undo: restore store to {:.8}", target),
+        )?;
 
-        Ok(temp_dir)
+        Ok(target)
     }
 
-    /// List tags on the local registry.
+    /// The newest commit that changed `index.toml` or `content/`, if any.
+    fn last_store_commit(&self) -> Result<Option<String>, RefstoreError> {
+        let mut touched = HashSet::new();
+        for path in STORE_PATHS {
+            touched.extend(self.git.log_path(&self.root, path)?.into_iter().map(|e| e.hash));
+        }
+        // The data dir's history is linear
+        let mut back = 0;
+        while !touched.is_empty()
+            && let Ok(commit) = self.git.rev_parse(&self.root, &format!("HEAD~{back}"))
+        {
+            if touched.contains(&commit) {
+                return Ok(Some(commit));
+            }
+            back += 1;
+        }
+        Ok(None)
+    }
+
+    // --- Object store ---
+
+    fn objects(&self) -> ObjectStore {
+        ObjectStore::new(&self.root)
+    }
+
+    /// Hardlink a remote registry's content into the object store, if enabled.
+    fn dedup_registry(&self, name: &str) -> Result<(), RefstoreError> {
+        if !self.config.dedup_content {
+            return Ok(());
+        }
+        let content = self.root.join("registries").join(name).join("content");
+        self.objects()
+            .dedup_dir(&content, &mut HashSet::new(), &mut ObjectStats::default())
+    }
+
+    /// With `dedup_content` on, link all local and remote registry content into
+    /// the object store, then delete objects nothing links to any more. With it
+    /// off, delete all objects (content files keep their data). Either way,
+    /// drop cached versions of references no registry defines any more.
+    pub fn gc(&self) -> Result<ObjectStats, RefstoreError> {
+        let objects = self.objects();
+        let mut seen = HashSet::new();
+        let mut stats = ObjectStats::default();
+
+        if self.config.dedup_content {
+            objects.dedup_dir(&self.root.join("content"), &mut seen, &mut stats)?;
+            for (name, _) in &self.remotes {
+                let content = self.root.join("registries").join(name).join("content");
+                objects.dedup_dir(&content, &mut seen, &mut stats)?;
+            }
+        }
+        objects.prune(&seen, &mut stats)?;
+        self.prune_version_cache(&mut stats)?;
+
+        Ok(stats)
+    }
+
+    /// Remove `cache/versions/<registry>/<commit>/<name>` entries whose
+    /// registry is gone or no longer defines `name`, then any commit and
+    /// registry directories left empty. Entries being extracted (dot-prefixed)
+    /// are left alone.
+    fn prune_version_cache(&self, stats: &mut ObjectStats) -> Result<(), RefstoreError> {
+        let versions = self.root.join("cache").join("versions");
+        for (registry_dir, registry) in read_dir_names(&versions)? {
+            let store = self.stores().find(|(name, _)| *name == registry).map(|(_, store)| store);
+            for (commit_dir, _) in read_dir_names(&registry_dir)? {
+                for (entry, name) in read_dir_names(&commit_dir)? {
+                    if name.starts_with('.') || store.is_some_and(|s| s.get(&name).is_some()) {
+                        continue;
+                    }
+                    fs::remove_dir_all(&entry).map_err(|source| RefstoreError::FileWrite { path: entry, source })?;
+                    stats.versions_pruned += 1;
+                }
+                // Only succeeds once the commit directory is empty
+                let _ = fs::remove_dir(&commit_dir);
+            }
+            let _ = fs::remove_dir(&registry_dir);
+        }
+        Ok(())
+    }
+
+    /// List registry-wide tags on the local registry. Release tags of single
+    /// references are listed by `releases` instead.
     pub fn list_tags(&self) -> Result<Vec<String>, RefstoreError> {
-        git::list_tags(&self.root)
+        registry_wide_tags(&*self.git, &self.root)
+    }
+
+    /// Registry-wide tags of the registry that owns `name`.
+    pub fn registry_tags(&self, name: &str) -> Result<Vec<String>, RefstoreError> {
+        match self.history_root(name)? {
+            Some(root) => registry_wide_tags(&*self.git, root),
+            None => Ok(Vec::new()),
+        }
     }
 
     /// Create a tag on the local registry.
     pub fn create_tag(&self, tag: &str, message: Option<&str>) -> Result<(), RefstoreError> {
-        git::create_tag(&self.root, tag, message)
+        self.git.create_tag(&self.root, tag, message)
     }
 
     // --- Push to another registry ---
 
-    /// Copy a reference from the local registry to another registry at the given path.
-    pub fn push_to(&self, name: &str, target_path: &Path) -> Result<(), RefstoreError> {
+    /// Copy a reference from the local registry to another registry at the given
+    /// path. Unless waived by `checks`, the content must pass the size limits
+    /// and the secret scan against the target's allowlist.
+    pub fn push_to(
+        &self,
+        name: &str,
+        target_path: &Path,
+        checks: ContentChecks,
+    ) -> Result<ContentReport, RefstoreError> {
         let reference = self
             .local
             .get(name)
@@ -539,41 +1385,122 @@ impl RepositoryStore {
             });
         }
 
+        if !checks.allow_secrets {
+            check_secrets(target_path, name, &source_content)?;
+        }
+
         let target_content = target.content_path(name);
-        copy_dir_recursive(&source_content, &target_content)?;
+        let report = copy_dir_recursive(
+            &source_content,
+            &target_content,
+            &self.content_policy(checks),
+            name,
+        )?;
 
         target.index_mut().references.insert(reference.name.clone(), reference);
         target.save_index()?;
 
         let content_rel = format!("content/{name}");
-        git::commit(target_path, &[&content_rel, "index.toml"], &format!("Add reference: {name}"))?;
+        self.git.commit(target_path, &[&content_rel, "index.toml"], &format!("Add reference: {name}"))?;
 
-        Ok(())
+        Ok(report)
     }
 
     // --- Content fetching ---
 
+    /// Where the bare clone of a git upstream is cached.
+    fn mirror_dir(&self, url: &str) -> PathBuf {
+        let digest = Sha256::digest(url.as_bytes());
+        let key: String = digest[..8].iter().map(|b| format!("{b:02x}")).collect();
+        self.root.join("cache").join("git").join(key)
+    }
+
+    /// Fetch into a mirror at most once per process, so references sharing an
+    /// upstream share one fetch during `store update`.
+    fn refresh_mirror(&self, url: &str, mirror: &Path) -> Result<(), RefstoreError> {
+        let mut fetched = self.fetched_mirrors.lock().unwrap_or_else(|e| e.into_inner());
+        if !fetched.contains(mirror) {
+            git::update_mirror(url, mirror)?;
+            fetched.insert(mirror.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Fetch a reference's content into `content_dir`, running its build step
+    /// if it has one. Returns the upstream version: the commit for git sources,
+    /// or whatever a source helper reported.
     fn fetch_content(
         &self,
         reference: &Reference,
         content_dir: &Path,
-    ) -> Result<(), RefstoreError> {
+        policy: &ContentPolicy,
+    ) -> Result<(Option<String>, ContentReport), RefstoreError> {
+        let (upstream_rev, report) = match &reference.build {
+            Some(build) => {
+                // Build in a fresh temp checkout so only the output reaches content/,
+                // and apply the policy to that output rather than the sources
+                let scratch = tempfile::Builder::new()
+                    .prefix("refstore-build-")
+                    .tempdir()
+                    .map_err(|source| RefstoreError::DirCreate {
+                        path: std::env::temp_dir(),
+                        source,
+                    })?;
+                let checkout = scratch.path().join("src");
+                let (rev, _) = self.fetch_source(reference, &checkout, &ContentPolicy::unrestricted())?;
+                run_build(&reference.name, build, scratch.path(), content_dir)?;
+                (rev, enforce_in_place(policy, &reference.name, content_dir)?)
+            }
+            None => self.fetch_source(reference, content_dir, policy)?,
+        };
+
+        if !reference.extract.is_empty() {
+            extract::extract_dir(content_dir, &reference.extract)?;
+        }
+        if self.config.dedup_content {
+            self.objects()
+                .dedup_dir(content_dir, &mut HashSet::new(), &mut ObjectStats::default())?;
+        }
+        Ok((upstream_rev, report))
+    }
+
+    fn content_policy(&self, checks: ContentChecks) -> ContentPolicy {
+        if checks.force {
+            ContentPolicy::unrestricted()
+        } else {
+            ContentPolicy::from_config(&self.config)
+        }
+    }
+
+    /// Fetch a reference's source into `content_dir`, subject to `policy`.
+    fn fetch_source(
+        &self,
+        reference: &Reference,
+        content_dir: &Path,
+        policy: &ContentPolicy,
+    ) -> Result<(Option<String>, ContentReport), RefstoreError> {
+        let mut upstream_rev = None;
+        let report;
         match &reference.source {
             ReferenceSource::Local { path } => {
                 if path.is_file() {
+                    // Check before copying so an oversized file is never read
+                    report = policy.check(&reference.name, path)?;
                     fs::create_dir_all(content_dir).map_err(|source| {
                         RefstoreError::DirCreate {
                             path: content_dir.to_path_buf(),
                             source,
                         }
                     })?;
-                    let dest = content_dir.join(path.file_name().unwrap_or("file".as_ref()));
-                    fs::copy(path, &dest).map_err(|source| RefstoreError::FileRead {
-                        path: path.clone(),
-                        source,
-                    })?;
+                    if report.skipped.is_empty() {
+                        let dest = content_dir.join(path.file_name().unwrap_or("file".as_ref()));
+                        fs::copy(path, &dest).map_err(|source| RefstoreError::FileRead {
+                            path: path.clone(),
+                            source,
+                        })?;
+                    }
                 } else if path.is_dir() {
-                    copy_dir_recursive(path, content_dir)?;
+                    report = copy_dir_recursive(path, content_dir, policy, &reference.name)?;
                 } else {
                     return Err(RefstoreError::FileRead {
                         path: path.clone(),
@@ -584,16 +1511,48 @@ impl RepositoryStore {
                     });
                 }
             }
-            ReferenceSource::Git { url, r#ref, .. } => {
-                git::ensure_git()?;
-                git::clone_shallow(
-                    url,
-                    content_dir,
-                    r#ref.as_deref(),
-                    self.config.git_depth,
-                )?;
+            ReferenceSource::Git {
+                url,
+                r#ref,
+                subpath,
+                ref_kind,
+                recurse_submodules,
+                lfs,
+            } => {
+                ensure_git_for_source(&reference.name)?;
+                let options = git::CheckoutOptions {
+                    recurse_submodules: *recurse_submodules,
+                    lfs: *lfs,
+                };
+                let git_ref = r#ref.as_deref().or(self.config.default_branch.as_deref());
+                if self.config.git_cache {
+                    let mirror = self.mirror_dir(url);
+                    self.refresh_mirror(url, &mirror)?;
+                    let commit = git::resolve_in_mirror(&mirror, url, git_ref, *ref_kind)?;
+                    git::checkout_from_mirror(&mirror, url, &commit, content_dir, options)?;
+                } else {
+                    match (r#ref, ref_kind) {
+                        (Some(commit), Some(GitRefKind::Commit)) => {
+                            git::clone_at_commit(url, content_dir, commit, options)?;
+                        }
+                        _ => {
+                            self.git.clone(
+                                url,
+                                content_dir,
+                                git_ref,
+                                self.config.git_depth,
+                                options,
+                            )?;
+                        }
+                    }
+                }
+                upstream_rev = Some(git::head_hash(content_dir)?);
                 // Strip .git/ so we don't have nested git repos in the registry
                 git::strip_git_dir(content_dir)?;
+                if let Some(subpath) = subpath {
+                    apply_subpath(&reference.name, content_dir, subpath)?;
+                }
+                report = enforce_in_place(policy, &reference.name, content_dir)?;
             }
             ReferenceSource::Remote { url } => {
                 return Err(RefstoreError::SyncFailed {
@@ -601,8 +1560,23 @@ impl RepositoryStore {
                     reason: format!("remote sources not yet supported: {url}"),
                 });
             }
+            ReferenceSource::Inline => return Err(inline_not_fetchable(&reference.name)),
+            ReferenceSource::Plugin { scheme, args } => {
+                fs::create_dir_all(content_dir).map_err(|source| RefstoreError::DirCreate {
+                    path: content_dir.to_path_buf(),
+                    source,
+                })?;
+                upstream_rev = plugin::fetch(
+                    scheme,
+                    args,
+                    &reference.name,
+                    content_dir,
+                    reference.checksum.as_deref(),
+                )?;
+                report = enforce_in_place(policy, &reference.name, content_dir)?;
+            }
         }
-        Ok(())
+        Ok((upstream_rev, report))
     }
 }
 
@@ -625,35 +1599,172 @@ fn load_config(root: &Path) -> Result<GlobalConfig, RefstoreError> {
     Ok(config)
 }
 
-/// Scan the registries/ directory for submodule registries.
-fn load_remote_registries(root: &Path) -> Vec<(String, RegistryStore)> {
-    let registries_dir = root.join("registries");
-    if !registries_dir.exists() {
-        return Vec::new();
-    }
+/// The layers named by `REFSTORE_PATH`, or by the config when it is unset.
+/// `REFSTORE_PATH` is a path list like `PATH`; entries are `name=/path` or a bare
+/// path, which is named after its last component. Missing layers are skipped so
+/// an unmounted share doesn't break every command. Layer names must be distinct
+/// from each other, from `local` and from the `remotes`, so that
+/// `<registry>/<name>` picks out exactly one store.
+fn layer_dirs(
+    config: &GlobalConfig,
+    remotes: &[(String, PathBuf)],
+) -> Result<Vec<(String, PathBuf)>, RefstoreError> {
+    let layers: Vec<Layer> = match std::env::var_os("REFSTORE_PATH") {
+        Some(value) => std::env::split_paths(&value)
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(|entry| {
+                let text = entry.to_string_lossy();
+                // Only a valid name before `=` makes a `name=/path` entry, so
+                // a path like `/mnt/a=b/refs` stays a path
+                match text.split_once('=').filter(|(name, _)| validate_name(name).is_ok()) {
+                    Some((name, path)) => Layer {
+                        name: name.to_string(),
+                        path: PathBuf::from(path),
+                    },
+                    None => Layer {
+                        name: entry
+                            .file_name()
+                            .map(|n| n.to_string_lossy().to_string())
+                            .unwrap_or_else(|| text.to_string()),
+                        path: entry.clone(),
+                    },
+                }
+            })
+            .collect(),
+        None => config.layers.clone(),
+    };
 
     let mut result = Vec::new();
-    let entries = match fs::read_dir(&registries_dir) {
+    for (i, layer) in layers.iter().enumerate() {
+        let reason = if layer.name == "local" {
+            Some("'local' is reserved for the local registry")
+        } else if remotes.iter().any(|(name, _)| *name == layer.name) {
+            Some("a layer can't share its name with a remote registry")
+        } else if layers[..i].iter().any(|other| other.name == layer.name) {
+            Some("two layers can't share a name")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(RefstoreError::InvalidName {
+                name: layer.name.clone(),
+                reason: reason.to_string(),
+            });
+        }
+        if layer.path.join("index.toml").exists() {
+            result.push((layer.name.clone(), layer.path.clone()));
+        }
+    }
+    Ok(result)
+}
+
+/// Scan the registries/ directory for submodule registries, in resolution order.
+fn remote_registry_dirs(root: &Path, config: &GlobalConfig) -> Vec<(String, PathBuf)> {
+    let entries = match fs::read_dir(root.join("registries")) {
         Ok(e) => e,
         Err(_) => return Vec::new(),
     };
 
-    for entry in entries.flatten() {
-        if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
-            let name = entry.file_name().to_string_lossy().to_string();
-            let path = entry.path();
-            // Only load if it has an index.toml (i.e., is a valid registry)
-            if path.join("index.toml").exists() {
-                if let Ok(store) = RegistryStore::open(&path) {
-                    result.push((name, store));
-                }
-            }
+    let mut result: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        // Only a directory with an index.toml is a valid registry
+        .filter(|entry| entry.path().join("index.toml").exists())
+        .map(|entry| (entry.file_name().to_string_lossy().to_string(), entry.path()))
+        .collect();
+
+    result.sort_by(|(a, _), (b, _)| resolution_key(config, a).cmp(&resolution_key(config, b)));
+    result
+}
+
+/// Remotes resolve by descending priority, then by name.
+fn resolution_key<'a>(config: &GlobalConfig, name: &'a str) -> (Reverse<i32>, &'a str) {
+    (Reverse(config.registry_priority(name)), name)
+}
+
+/// Open the submodule registries, skipping any whose index doesn't parse. An
+/// index in a newer format than this build supports is an error instead, so
+/// the user is told to upgrade rather than losing the registry without a word.
+fn load_remote_registries(
+    root: &Path,
+    config: &GlobalConfig,
+) -> Result<Vec<(String, RegistryStore)>, RefstoreError> {
+    let mut result = Vec::new();
+    for (name, path) in remote_registry_dirs(root, config) {
+        match RegistryStore::open(&path) {
+            Ok(store) => result.push((name, store)),
+            Err(e @ RefstoreError::UnsupportedFormatVersion { .. }) => return Err(e),
+            Err(_) => {}
         }
     }
+    Ok(result)
+}
 
-    // Sort by name for consistent resolution order
-    result.sort_by(|(a, _), (b, _)| a.cmp(b));
-    result
+fn registry_wide_tags(git: &dyn GitBackend, root: &Path) -> Result<Vec<String>, RefstoreError> {
+    let tags = git.tags(root, "")?;
+    Ok(tags
+        .into_iter()
+        .map(|t| t.name)
+        .filter(|t| release::parse_tag(t).is_none())
+        .collect())
+}
+
+/// Record whether a git source's ref is a branch, tag, or commit.
+/// No ref means the default branch, which always moves. Git sources are
+/// fetched with the `git` command whatever `git_backend` says, so this also
+/// fails early if it is missing.
+fn resolve_ref_kind(name: &str, source: &mut ReferenceSource) -> Result<(), RefstoreError> {
+    if let ReferenceSource::Git { .. } = source {
+        ensure_git_for_source(name)?;
+    }
+    if let ReferenceSource::Git {
+        url,
+        r#ref,
+        ref_kind: ref_kind @ None,
+        ..
+    } = source
+    {
+        *ref_kind = Some(match r#ref {
+            Some(r) => git::classify_ref(url, r)?,
+            None => GitRefKind::Branch,
+        });
+    }
+    Ok(())
+}
+
+/// Paths and names of the entries in `dir`, or none if it doesn't exist.
+fn read_dir_names(dir: &Path) -> Result<Vec<(PathBuf, String)>, RefstoreError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => return Err(RefstoreError::FileRead { path: dir.to_path_buf(), source }),
+    };
+    entries
+        .map(|entry| {
+            let entry = entry.map_err(|source| RefstoreError::FileRead { path: dir.to_path_buf(), source })?;
+            Ok((entry.path(), entry.file_name().to_string_lossy().into_owned()))
+        })
+        .collect()
+}
+
+/// Shorten a full commit hash to 8 characters; leave other versions alone.
+fn abbreviate(version: &str) -> &str {
+    if version.len() == 40 && version.chars().all(|c| c.is_ascii_hexdigit()) {
+        &version[..8]
+    } else {
+        version
+    }
+}
+
+fn ensure_git_for_source(name: &str) -> Result<(), RefstoreError> {
+    git::ensure_git().map_err(|_| RefstoreError::GitSourceNeedsGit { name: name.to_string() })
+}
+
+fn inline_not_fetchable(name: &str) -> RefstoreError {
+    RefstoreError::SyncFailed {
+        name: name.to_string(),
+        reason: "snippets have no source to fetch; use `refstore store edit-snippet`".to_string(),
+    }
 }
 
 fn validate_name(name: &str) -> Result<(), RefstoreError> {
@@ -663,6 +1774,12 @@ fn validate_name(name: &str) -> Result<(), RefstoreError> {
             reason: "name cannot be empty".to_string(),
         });
     }
+    if name == "." || name == ".." {
+        return Err(RefstoreError::InvalidName {
+            name: name.to_string(),
+            reason: "name cannot be '.' or '..'".to_string(),
+        });
+    }
     if !name
         .chars()
         .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
@@ -676,7 +1793,199 @@ fn validate_name(name: &str) -> Result<(), RefstoreError> {
     Ok(())
 }
 
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), RefstoreError> {
+/// Replace a checkout in `content_dir` with just its `subpath` directory.
+fn apply_subpath(name: &str, content_dir: &Path, subpath: &Path) -> Result<(), RefstoreError> {
+    let not_found = || RefstoreError::SyncFailed {
+        name: name.to_string(),
+        reason: format!("subpath '{}' not found in repository", subpath.display()),
+    };
+    if !is_plain_relative(subpath) {
+        return Err(not_found());
+    }
+    let source = content_dir.join(subpath);
+    if !source.is_dir() {
+        return Err(not_found());
+    }
+
+    let staging = content_dir.with_file_name(format!(".{name}.subpath"));
+    let _ = fs::remove_dir_all(&staging);
+    let moved = fs::rename(&source, &staging)
+        .and_then(|()| fs::remove_dir_all(content_dir))
+        .and_then(|()| fs::rename(&staging, content_dir));
+    moved.map_err(|source| RefstoreError::DirCreate {
+        path: content_dir.to_path_buf(),
+        source,
+    })
+}
+
+/// `PATH` for build commands: the system directories, without the user's own.
+const BUILD_PATH: &str = if cfg!(windows) {
+    r"C:\Windows\System32;C:\Windows"
+} else {
+    "/usr/local/bin:/usr/bin:/bin"
+};
+
+/// Run a reference's build command in the checkout at `scratch/src`, then
+/// move its output to `content_dir`.
+///
+/// The command gets a cleared environment: a minimal `PATH`, `LANG`, and a
+/// `HOME` of its own under `scratch`, so it can't pick up the user's tokens
+/// or dotfiles through them. Sandboxing is not implemented: the command still
+/// runs as the user, with their filesystem permissions and network access.
+/// Links in the output are refused, since the command decides where they point.
+fn run_build(name: &str, build: &BuildStep, scratch: &Path, content_dir: &Path) -> Result<(), RefstoreError> {
+    let failed = |reason: String| RefstoreError::SyncFailed {
+        name: name.to_string(),
+        reason,
+    };
+    let checkout = scratch.join("src");
+    let home = scratch.join("home");
+    fs::create_dir_all(&home).map_err(|source| RefstoreError::DirCreate {
+        path: home.clone(),
+        source,
+    })?;
+
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", &build.cmd]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", &build.cmd]);
+        cmd
+    };
+    cmd.env_clear()
+        .env("PATH", BUILD_PATH)
+        .env("HOME", &home)
+        .env("LANG", "C.UTF-8")
+        .env("REFSTORE_REFERENCE", name);
+    if cfg!(windows)
+        && let Some(system_root) = std::env::var_os("SystemRoot")
+    {
+        // cmd.exe can't start without it
+        cmd.env("SystemRoot", system_root);
+    }
+    let output = cmd
+        .current_dir(&checkout)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| failed(format!("could not run build command `{}`: {e}", build.cmd)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failed(format!(
+            "build command `{}` failed ({}): {}",
+            build.cmd,
+            output.status,
+            stderr.trim()
+        )));
+    }
+
+    let built = checkout.join(&build.output);
+    let not_found = || failed(format!("build output '{}' not found", build.output.display()));
+    if !is_plain_relative(&build.output) {
+        return Err(not_found());
+    }
+    // The command controls the checkout, so the output may be a link to
+    // anything readable; only accept a real file or directory inside it
+    let meta = fs::symlink_metadata(&built).map_err(|_| not_found())?;
+    let inside = built
+        .canonicalize()
+        .ok()
+        .zip(checkout.canonicalize().ok())
+        .is_some_and(|(built, checkout)| built.starts_with(checkout));
+    if meta.file_type().is_symlink() || !inside {
+        return Err(failed(format!(
+            "build output '{}' must be a file or directory inside the checkout, not a link",
+            build.output.display()
+        )));
+    }
+
+    // A link inside the output could expose any file the user can read, once
+    // copied or committed
+    if meta.is_dir() {
+        for entry in walkdir::WalkDir::new(&built).min_depth(1) {
+            let entry = entry.map_err(|e| RefstoreError::FileRead {
+                path: built.clone(),
+                source: e.into(),
+            })?;
+            if entry.path_is_symlink() {
+                return Err(failed(format!(
+                    "build output '{}' contains a link, '{}'; links can't be stored from a build",
+                    build.output.display(),
+                    entry.path().strip_prefix(&built).unwrap_or(entry.path()).display()
+                )));
+            }
+        }
+    }
+
+    // The temp dir is usually on another filesystem, where rename fails
+    if meta.is_dir() {
+        fs::rename(&built, content_dir).or_else(|_| {
+            copy_dir_recursive(&built, content_dir, &ContentPolicy::unrestricted(), name).map(|_| ())
+        })
+    } else if meta.is_file() {
+        let target = content_dir.join(built.file_name().unwrap_or_default());
+        fs::create_dir_all(content_dir).map_err(|source| RefstoreError::DirCreate {
+            path: content_dir.to_path_buf(),
+            source,
+        })?;
+        fs::rename(&built, &target)
+            .or_else(|_| fs::copy(&built, &target).map(|_| ()))
+            .map_err(|source| RefstoreError::FileWrite { path: target, source })
+    } else {
+        Err(not_found())
+    }
+}
+
+/// Fail with every finding if `dir`, to be stored as `content/<name>` in the
+/// registry at `registry_root`, contains likely credentials.
+fn check_secrets(registry_root: &Path, name: &str, dir: &Path) -> Result<(), RefstoreError> {
+    let prefix = Path::new("content").join(name);
+    let findings = Scanner::for_registry(registry_root)?.scan_dir(dir, &prefix)?;
+    secrets_result(name, findings)
+}
+
+/// Like `check_secrets`, for the text of snippet `name`.
+fn check_snippet_secrets(registry_root: &Path, name: &str, text: &str) -> Result<(), RefstoreError> {
+    let path = Path::new("content").join(name).join(format!("{name}.md"));
+    secrets_result(name, Scanner::for_registry(registry_root)?.scan_text(text, &path))
+}
+
+fn secrets_result(name: &str, findings: Vec<Finding>) -> Result<(), RefstoreError> {
+    if findings.is_empty() {
+        return Ok(());
+    }
+    Err(RefstoreError::SecretsFound {
+        name: name.to_string(),
+        findings: findings.iter().map(|f| format!("  {f}")).collect::<Vec<_>>().join("\n"),
+    })
+}
+
+/// A relative path with no `..`, so joining it never leaves the base directory.
+fn is_plain_relative(path: &Path) -> bool {
+    path.components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)))
+}
+
+/// Apply `policy` to content already fetched into `dir`, deleting skipped files.
+fn enforce_in_place(policy: &ContentPolicy, name: &str, dir: &Path) -> Result<ContentReport, RefstoreError> {
+    let report = policy.check(name, dir)?;
+    for relative in &report.skipped {
+        let path = dir.join(relative);
+        fs::remove_file(&path).map_err(|source| RefstoreError::FileWrite { path, source })?;
+    }
+    Ok(report)
+}
+
+/// Copy `src` into `dst`, checking it against `policy` first so nothing is
+/// copied if it is over the limits, and leaving out skipped binary files.
+fn copy_dir_recursive(
+    src: &Path,
+    dst: &Path,
+    policy: &ContentPolicy,
+    name: &str,
+) -> Result<ContentReport, RefstoreError> {
+    let report = policy.check(name, src)?;
     fs::create_dir_all(dst).map_err(|source| RefstoreError::DirCreate {
         path: dst.to_path_buf(),
         source,
@@ -689,6 +1998,9 @@ fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), RefstoreError> {
         })?;
 
         let relative = entry.path().strip_prefix(src).unwrap();
+        if report.skipped.iter().any(|s| s == relative) {
+            continue;
+        }
         let target = dst.join(relative);
 
         if entry.file_type().is_dir() {
@@ -703,5 +2015,5 @@ fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), RefstoreError> {
             })?;
         }
     }
-    Ok(())
+    Ok(report)
 }
@@ -0,0 +1,252 @@
+//! Store logic against the in-memory git backend.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+
+use super::RepositoryStore;
+use super::repository::ContentChecks;
+use crate::git::ChangeKind;
+use crate::git::memory::MemoryBackend;
+use crate::model::{Reference, ReferenceKind, ReferenceSource, RepositoryIndex};
+
+struct Fixture {
+    dir: tempfile::TempDir,
+    git: MemoryBackend,
+}
+
+impl Fixture {
+    /// A data dir plus a `source/` directory holding a README.
+    fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("source")).unwrap();
+        fs::write(dir.path().join("source/README.md"), "# Sample\n").unwrap();
+        Self {
+            dir,
+            git: MemoryBackend::default(),
+        }
+    }
+
+    fn source(&self) -> PathBuf {
+        self.dir.path().join("source")
+    }
+
+    fn open(&self) -> RepositoryStore {
+        RepositoryStore::open_with(self.dir.path().join("data"), Some(Box::new(self.git.clone()))).unwrap()
+    }
+
+    fn data(&self) -> PathBuf {
+        self.dir.path().join("data")
+    }
+
+    fn add(&self, store: &mut RepositoryStore, name: &str) {
+        store.add(self.reference(name, &[]), ContentChecks::default()).unwrap();
+    }
+
+    fn reference(&self, name: &str, tags: &[&str]) -> Reference {
+        Reference {
+            name: name.to_string(),
+            kind: ReferenceKind::Directory,
+            source: ReferenceSource::Local { path: self.source() },
+            description: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            added_at: Utc::now(),
+            last_synced: None,
+            checksum: None,
+            extract: Vec::new(),
+            build: None,
+        }
+    }
+
+    /// Write a remote registry checkout holding `references` under `registries/<name>`.
+    fn remote(&self, name: &str, references: &[Reference]) {
+        let dir = self.data().join("registries").join(name);
+        fs::create_dir_all(&dir).unwrap();
+        let index = RepositoryIndex {
+            references: references.iter().map(|r| (r.name.clone(), r.clone())).collect(),
+            ..Default::default()
+        };
+        fs::write(dir.join("index.toml"), toml::to_string_pretty(&index).unwrap()).unwrap();
+    }
+}
+
+fn messages(store: &RepositoryStore) -> Vec<String> {
+    store.history(20).unwrap().into_iter().map(|h| h.log.message).collect()
+}
+
+fn read(path: &Path) -> String {
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn reopening_commits_nothing_new() {
+    let fixture = Fixture::new();
+    fixture.open();
+    let store = fixture.open();
+
+    assert_eq!(messages(&store), ["Initialize refstore repository"]);
+}
+
+#[test]
+fn add_and_remove_are_committed() {
+    let fixture = Fixture::new();
+    let mut store = fixture.open();
+
+    fixture.add(&mut store, "my-docs");
+    store.remove("my-docs").unwrap();
+
+    assert_eq!(
+        messages(&store),
+        [
+            "Remove reference: my-docs",
+            "Add reference: my-docs",
+            "Initialize refstore repository"
+        ]
+    );
+    assert_eq!(store.versions("my-docs").unwrap_err().to_string(), "reference 'my-docs' not found in repository");
+}
+
+#[test]
+fn update_reports_changed_files() {
+    let fixture = Fixture::new();
+    let mut store = fixture.open();
+    fixture.add(&mut store, "my-docs");
+
+    fs::write(fixture.source().join("README.md"), "# Sample\n\nMore.\n").unwrap();
+    fs::write(fixture.source().join("guide.md"), "# Guide\n").unwrap();
+    let summary = store.update("my-docs", ContentChecks::default()).unwrap();
+
+    let changes: Vec<_> = summary
+        .changes
+        .iter()
+        .map(|c| (c.kind, c.path.as_str(), c.lines_added, c.lines_removed))
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            (ChangeKind::Modified, "README.md", Some(1), Some(0)),
+            (ChangeKind::Added, "guide.md", Some(1), Some(0)),
+        ]
+    );
+    assert_eq!(store.versions("my-docs").unwrap().len(), 2);
+}
+
+#[test]
+fn undo_brings_back_a_removed_reference() {
+    let fixture = Fixture::new();
+    let mut store = fixture.open();
+    fixture.add(&mut store, "my-docs");
+    store.remove("my-docs").unwrap();
+
+    store.undo(None).unwrap();
+
+    assert!(store.get("my-docs").is_some());
+    assert_eq!(read(&store.content_path("my-docs").join("README.md")), "# Sample\n");
+    assert_eq!(messages(&store)[0].get(..22), Some("Undo: restore store to"));
+}
+
+#[test]
+fn undo_to_the_current_state_is_nothing_to_undo() {
+    let fixture = Fixture::new();
+    let mut store = fixture.open();
+    fixture.add(&mut store, "my-docs");
+
+    let err = store.undo(Some("HEAD")).unwrap_err();
+    assert!(matches!(err, crate::error::RefstoreError::NothingToUndo { .. }), "{err}");
+}
+
+#[test]
+fn releases_pin_content_across_updates() {
+    let fixture = Fixture::new();
+    let mut store = fixture.open();
+    fixture.add(&mut store, "my-docs");
+    store.release("my-docs", &semver::Version::new(1, 0, 0), None).unwrap();
+
+    fs::write(fixture.source().join("README.md"), "# Changed\n").unwrap();
+    store.update("my-docs", ContentChecks::default()).unwrap();
+
+    let resolved = store.resolve_version("my-docs", "^1").unwrap();
+    assert_eq!(resolved.version, Some(semver::Version::new(1, 0, 0)));
+    let content = store.content_at_version("my-docs", &resolved).unwrap();
+    assert_eq!(read(&content.join("README.md")), "# Sample\n");
+    assert_eq!(read(&store.content_path("my-docs").join("README.md")), "# Changed\n");
+}
+
+#[test]
+fn index_cache_is_written_and_read_back() {
+    let fixture = Fixture::new();
+    let mut store = fixture.open();
+    fixture.add(&mut store, "my-docs");
+    let sources = [("local".to_string(), fixture.data())];
+    assert!(super::index_cache::load(&fixture.data(), &sources).is_some());
+
+    let store = RepositoryStore::open_read_only(Some(&fixture.data())).unwrap();
+    assert_eq!(store.resolve("my-docs").unwrap().registry_name, "local");
+}
+
+#[test]
+fn index_cache_notices_edited_indexes() {
+    let fixture = Fixture::new();
+    fixture.remote("team", &[fixture.reference("team-docs", &[])]);
+    let mut store = fixture.open();
+    fixture.add(&mut store, "my-docs");
+
+    fixture.remote("team", &[fixture.reference("other-docs", &[])]);
+    fs::write(fixture.data().join("index.toml"), "version = 1\n").unwrap();
+
+    let store = RepositoryStore::open_read_only(Some(&fixture.data())).unwrap();
+    let names: Vec<_> = store.list(None, None).iter().map(|r| r.reference.name.clone()).collect();
+    assert_eq!(names, ["other-docs"]);
+}
+
+#[test]
+fn index_cache_rehashes_indexes_as_new_as_the_cache() {
+    let fixture = Fixture::new();
+    let mut store = fixture.open();
+    fixture.add(&mut store, "my-docs");
+    let sources = [("local".to_string(), fixture.data())];
+    let index = fixture.data().join("index.toml");
+    let cache = fixture.data().join("cache/index.json");
+    let set_modified = |path: &Path, time| fs::File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+
+    // Older than the cache: size and mtime are trusted
+    let modified = fs::metadata(&index).unwrap().modified().unwrap();
+    set_modified(&cache, modified + std::time::Duration::from_secs(1));
+    assert!(super::index_cache::load(&fixture.data(), &sources).is_some());
+
+    // Same size and mtime, but written in the cache's timestamp tick
+    let edited = fs::read_to_string(&index).unwrap().replace("my-docs", "my-doks");
+    fs::write(&index, edited).unwrap();
+    set_modified(&index, modified);
+    set_modified(&cache, modified);
+    assert!(super::index_cache::load(&fixture.data(), &sources).is_none());
+}
+
+#[test]
+fn merged_index_prefers_earlier_registries() {
+    let fixture = Fixture::new();
+    fixture.remote("a-team", &[fixture.reference("shared", &["rust"]), fixture.reference("a-only", &["rust"])]);
+    fixture.remote("b-team", &[fixture.reference("shared", &["rust"]), fixture.reference("b-only", &[])]);
+    let mut store = fixture.open();
+    store.add(fixture.reference("shared", &[]), ContentChecks::default()).unwrap();
+
+    for store in [store, fixture.open()] {
+        let listed: Vec<_> = store
+            .list(None, None)
+            .iter()
+            .map(|r| (r.reference.name.clone(), r.registry_name.to_string()))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("shared".to_string(), "local".to_string()),
+                ("a-only".to_string(), "a-team".to_string()),
+                ("b-only".to_string(), "b-team".to_string()),
+            ]
+        );
+        // Tags come from the winning copy, which has none
+        let tagged: Vec<_> = store.list(Some("rust"), None).iter().map(|r| r.reference.name.clone()).collect();
+        assert_eq!(tagged, ["a-only"]);
+    }
+}
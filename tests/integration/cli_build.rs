@@ -0,0 +1,184 @@
+#![cfg(unix)]
+
+use std::fs;
+
+use predicates::prelude::*;
+
+use crate::common::{TestEnv, commit_files};
+
+#[test]
+fn build_output_replaces_source_content() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+
+    env.cmd()
+        .args(["store", "add", "book", "--build-cmd"])
+        .arg("mkdir -p out && cat README.md docs/guide.md > out/combined.md")
+        .args(["--build-output", "out/"])
+        .arg(&sample)
+        .assert()
+        .success();
+
+    let content = env.data_dir.path().join("content/book");
+    assert_eq!(
+        fs::read_to_string(content.join("combined.md")).unwrap(),
+        "# Sample Reference\n# Guide\n"
+    );
+    assert!(!content.join("README.md").exists(), "only the build output is cached");
+    assert!(!sample.join("out").exists(), "the build must not touch the source");
+    assert!(!env.data_dir.path().join("cache/build/book").exists());
+
+    env.cmd()
+        .args(["info", "book"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Build:       `mkdir -p out"));
+}
+
+#[test]
+fn build_single_file_output() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+
+    env.cmd()
+        .args(["store", "add", "api", "--build-cmd", "cat src/*.rs > api.rs"])
+        .args(["--build-output", "api.rs"])
+        .arg(&sample)
+        .assert()
+        .success();
+
+    let built = env.data_dir.path().join("content/api/api.rs");
+    assert_eq!(
+        fs::read_to_string(built).unwrap(),
+        "pub fn hello() {}\npub fn util() {}\n"
+    );
+}
+
+#[test]
+fn build_failure_reports_stderr() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+
+    env.cmd()
+        .args(["store", "add", "broken", "--build-cmd", "echo boom >&2; exit 3"])
+        .args(["--build-output", "out"])
+        .arg(&sample)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("sync failed for 'broken'"))
+        .stderr(predicate::str::contains("boom"));
+
+    assert!(!env.data_dir.path().join("content/broken").exists());
+    assert!(!env.data_dir.path().join("cache/build/broken").exists());
+}
+
+#[test]
+fn build_missing_output_fails() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+
+    env.cmd()
+        .args(["store", "add", "nothing", "--build-cmd", "true"])
+        .args(["--build-output", "book"])
+        .arg(&sample)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("build output 'book' not found"));
+}
+
+#[test]
+fn build_reruns_on_update() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("VERSION", "1\n")]);
+
+    env.cmd()
+        .args(["store", "add", "versioned", "--build-cmd"])
+        .arg("mkdir site && sed 's/^/v/' VERSION > site/index.md")
+        .args(["--build-output", "site"])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    commit_files(&upstream, &[("VERSION", "2\n")], "bump");
+    env.cmd()
+        .args(["store", "update", "versioned"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 modified"));
+
+    let index = env.data_dir.path().join("content/versioned/index.md");
+    assert_eq!(fs::read_to_string(index).unwrap(), "v2\n");
+}
+
+#[test]
+fn build_requires_output() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+
+    env.cmd()
+        .args(["store", "add", "book", "--build-cmd", "true"])
+        .arg(&sample)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--build-output"));
+}
+
+#[test]
+fn build_runs_with_a_cleared_environment() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+
+    env.cmd()
+        .env("REFSTORE_TEST_TOKEN", "hunter2")
+        .args(["store", "add", "envdump", "--build-cmd", "env > env.txt"])
+        .args(["--build-output", "env.txt"])
+        .arg(&sample)
+        .assert()
+        .success();
+
+    let dumped = fs::read_to_string(env.data_dir.path().join("content/envdump/env.txt")).unwrap();
+    assert!(!dumped.contains("hunter2"), "{dumped}");
+    assert!(dumped.contains("PATH=/usr/local/bin:/usr/bin:/bin\n"), "{dumped}");
+    assert!(dumped.contains("REFSTORE_REFERENCE=envdump\n"), "{dumped}");
+    let home = dumped.lines().find_map(|l| l.strip_prefix("HOME=")).unwrap();
+    assert!(home.contains("refstore-build-"), "{home}");
+}
+
+#[test]
+fn build_output_must_not_be_a_link() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    let outside = env.data_dir.path().join("outside");
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(outside.join("secret"), "s3cret\n").unwrap();
+
+    env.cmd()
+        .args(["store", "add", "leak", "--build-cmd"])
+        .arg(format!("ln -s {} out", outside.display()))
+        .args(["--build-output", "out"])
+        .arg(&sample)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a link"));
+    assert!(!env.data_dir.path().join("content/leak").exists());
+}
+
+#[test]
+fn build_output_must_not_contain_links() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    let secret = env.data_dir.path().join("secret");
+    fs::write(&secret, "s3cret\n").unwrap();
+
+    for (name, link) in [("absolute", secret.display().to_string()), ("relative", "../../secret".to_string())] {
+        env.cmd()
+            .args(["store", "add", name, "--build-cmd"])
+            .arg(format!("mkdir -p out/docs && echo hi > out/index.md && ln -s {link} out/docs/key"))
+            .args(["--build-output", "out"])
+            .arg(&sample)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("build output 'out' contains a link, 'docs/key'"));
+        assert!(!env.data_dir.path().join("content").join(name).exists());
+    }
+}
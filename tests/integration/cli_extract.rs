@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::Path;
+
+use lopdf::content::{Content, Operation};
+use lopdf::{Document, Object, Stream, dictionary};
+use predicates::prelude::*;
+
+use crate::common::TestEnv;
+
+/// Write a minimal PDF with one line of text per page.
+fn write_pdf(path: &Path, pages: &[&str]) {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Courier",
+    });
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+
+    let mut kids = Vec::new();
+    for text in pages {
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Td", vec![72.into(), 720.into()]),
+                Operation::new("Tj", vec![Object::string_literal(*text)]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        kids.push(page_id.into());
+    }
+
+    let pages_dict = dictionary! {
+        "Type" => "Pages",
+        "Count" => kids.len() as i64,
+        "Kids" => kids,
+        "Resources" => resources_id,
+        "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+    };
+    doc.objects.insert(pages_id, Object::Dictionary(pages_dict));
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).unwrap();
+}
+
+const NOTEBOOK: &str = r##"{
+  "cells": [
+    {"cell_type": "markdown", "metadata": {}, "source": ["# Example\n", "Some prose."]},
+    {"cell_type": "code", "metadata": {}, "execution_count": 1,
+     "source": "print(\"hi\")",
+     "outputs": [{"output_type": "stream", "name": "stdout", "text": ["hi\n"]}]}
+  ],
+  "metadata": {"language_info": {"name": "python"}},
+  "nbformat": 4,
+  "nbformat_minor": 5
+}"##;
+
+#[test]
+fn extract_notebook_to_markdown() {
+    let env = TestEnv::new();
+    let src = env.project_dir.path().join("nb");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("example.ipynb"), NOTEBOOK).unwrap();
+
+    env.cmd()
+        .args(["store", "add", "notebooks"])
+        .arg(&src)
+        .args(["--extract", "notebook"])
+        .assert()
+        .success();
+
+    let md = env.data_dir.path().join("content/notebooks/example.ipynb.md");
+    let content = fs::read_to_string(&md).expect("notebook should be extracted");
+    assert!(content.contains("# Example\nSome prose."));
+    assert!(content.contains("```python\nprint(\"hi\")\n```"));
+    assert!(content.contains("```text\nhi\n```"));
+    assert!(
+        env.data_dir.path().join("content/notebooks/example.ipynb").exists(),
+        "original notebook should be kept"
+    );
+}
+
+#[test]
+fn extract_pdf_with_page_markers() {
+    let env = TestEnv::new();
+    let src = env.project_dir.path().join("specs");
+    fs::create_dir_all(&src).unwrap();
+    write_pdf(&src.join("spec.pdf"), &["First page", "Second page"]);
+
+    env.cmd()
+        .args(["store", "add", "specs"])
+        .arg(&src)
+        .args(["--extract", "pdf"])
+        .assert()
+        .success();
+
+    let md = env.data_dir.path().join("content/specs/spec.pdf.md");
+    let content = fs::read_to_string(&md).expect("pdf should be extracted");
+    let first = content.find("<!-- page 1 -->").expect("page 1 marker");
+    let second = content.find("<!-- page 2 -->").expect("page 2 marker");
+    assert!(first < second);
+    assert!(content[first..second].contains("First page"));
+    assert!(content[second..].contains("Second page"));
+}
+
+#[test]
+fn extract_only_requested_formats() {
+    let env = TestEnv::new();
+    let src = env.project_dir.path().join("mixed");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("example.ipynb"), NOTEBOOK).unwrap();
+    write_pdf(&src.join("spec.pdf"), &["Only page"]);
+
+    env.cmd()
+        .args(["store", "add", "mixed"])
+        .arg(&src)
+        .args(["--extract", "pdf"])
+        .assert()
+        .success();
+
+    let content = env.data_dir.path().join("content/mixed");
+    assert!(content.join("spec.pdf.md").exists());
+    assert!(!content.join("example.ipynb.md").exists());
+
+    env.cmd()
+        .args(["info", "mixed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Extract:     pdf"));
+}
+
+#[test]
+fn extract_invalid_notebook_fails_add() {
+    let env = TestEnv::new();
+    let src = env.project_dir.path().join("broken");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("broken.ipynb"), "not json").unwrap();
+
+    env.cmd()
+        .args(["store", "add", "broken"])
+        .arg(&src)
+        .args(["--extract", "notebook"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid notebook JSON"));
+}
+
+#[test]
+fn extract_unknown_format_rejected() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+
+    env.cmd()
+        .args(["store", "add", "docs"])
+        .arg(&sample)
+        .args(["--extract", "docx"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown extract format"));
+}
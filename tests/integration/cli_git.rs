@@ -0,0 +1,395 @@
+use std::fs;
+
+use predicates::prelude::*;
+
+use crate::common::{TestEnv, commit_files, git};
+
+#[test]
+fn git_add_uses_default_branch_when_no_ref() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "main\n")]);
+    git(&upstream, &["checkout", "-b", "docs"]);
+    commit_files(&upstream, &[("README.md", "docs branch\n")], "docs");
+    git(&upstream, &["checkout", "main"]);
+
+    env.cmd()
+        .args(["config", "set", "default_branch", "docs"])
+        .assert()
+        .success();
+
+    env.cmd()
+        .args(["store", "add", "git-docs"])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    let readme = env.data_dir.path().join("content/git-docs/README.md");
+    assert_eq!(fs::read_to_string(readme).unwrap(), "docs branch\n");
+
+    env.cmd()
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("git-docs (git_repo, tracking docs)"));
+}
+
+#[test]
+fn git_branch_ref_is_tracked() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "v1\n")]);
+
+    env.cmd()
+        .args(["store", "add", "git-docs", "--ref", "main"])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    env.cmd()
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("git-docs (git_repo, tracking main)"));
+
+    commit_files(&upstream, &[("README.md", "v2\n")], "bump");
+    env.cmd()
+        .args(["store", "update"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 updated, 0 skipped"));
+
+    let readme = env.data_dir.path().join("content/git-docs/README.md");
+    assert_eq!(fs::read_to_string(readme).unwrap(), "v2\n");
+}
+
+#[test]
+fn git_tag_ref_is_fixed_and_skipped_by_update() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "v1\n")]);
+    git(&upstream, &["tag", "v1.0"]);
+
+    env.cmd()
+        .args(["store", "add", "git-docs", "--ref", "v1.0"])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    env.cmd()
+        .args(["info", "git-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ref:         fixed at tag v1.0"));
+
+    commit_files(&upstream, &[("README.md", "v2\n")], "bump");
+    env.cmd()
+        .args(["store", "update"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("git-docs: skipped (fixed at tag v1.0)"))
+        .stdout(predicate::str::contains("0 updated, 1 skipped"));
+
+    let readme = env.data_dir.path().join("content/git-docs/README.md");
+    assert_eq!(fs::read_to_string(readme).unwrap(), "v1\n");
+}
+
+#[test]
+fn git_commit_ref_checks_out_commit() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "v1\n")]);
+    let first = git(&upstream, &["rev-parse", "HEAD"]);
+    commit_files(&upstream, &[("README.md", "v2\n")], "bump");
+
+    env.cmd()
+        .args(["store", "add", "git-docs", "--ref", &first])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    let readme = env.data_dir.path().join("content/git-docs/README.md");
+    assert_eq!(fs::read_to_string(readme).unwrap(), "v1\n");
+
+    env.cmd()
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "git-docs (git_repo, fixed at commit {})",
+            &first[..8]
+        )));
+}
+
+#[test]
+fn git_unknown_ref_fails() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "v1\n")]);
+
+    env.cmd()
+        .args(["store", "add", "git-docs", "--ref", "no-such-branch"])
+        .arg(&upstream)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a branch, tag, or commit"));
+}
+
+/// An upstream whose docs live in a `docs` submodule pointing at `sub.git`.
+fn upstream_with_submodule(env: &TestEnv) -> (std::path::PathBuf, std::path::PathBuf) {
+    let sub = env.create_git_upstream("sub", &[("guide.md", "from submodule\n")]);
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "parent\n")]);
+    git(
+        &upstream,
+        &["-c", "protocol.file.allow=always", "submodule", "add", sub.to_str().unwrap(), "docs"],
+    );
+    git(&upstream, &["commit", "-m", "add docs submodule"]);
+    (upstream, sub)
+}
+
+#[test]
+fn git_submodules_skipped_by_default() {
+    let env = TestEnv::new();
+    let (upstream, _) = upstream_with_submodule(&env);
+
+    env.cmd()
+        .args(["store", "add", "git-docs"])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    let content = env.data_dir.path().join("content/git-docs");
+    assert!(content.join("README.md").exists());
+    assert!(!content.join("docs/guide.md").exists());
+}
+
+#[test]
+fn git_recurse_submodules_checks_them_out() {
+    let env = TestEnv::new();
+    let (upstream, _) = upstream_with_submodule(&env);
+
+    env.cmd()
+        .args(["store", "add", "git-docs", "--recurse-submodules"])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    let content = env.data_dir.path().join("content/git-docs");
+    assert_eq!(
+        fs::read_to_string(content.join("docs/guide.md")).unwrap(),
+        "from submodule\n"
+    );
+    assert!(!content.join("docs/.git").exists(), "submodule .git file should be stripped");
+
+    // Content must be committed as plain files, not as a gitlink
+    let tracked = git(env.data_dir.path(), &["ls-files", "content/git-docs/docs"]);
+    assert_eq!(tracked, "content/git-docs/docs/guide.md");
+
+    env.cmd()
+        .args(["info", "git-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Submodules:  recursive"));
+}
+
+#[test]
+fn git_failing_submodule_is_reported() {
+    let env = TestEnv::new();
+    let (upstream, sub) = upstream_with_submodule(&env);
+    fs::remove_dir_all(&sub).unwrap();
+
+    env.cmd()
+        .args(["store", "add", "git-docs", "--recurse-submodules"])
+        .arg(&upstream)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to fetch submodules of"));
+
+    assert!(!env.data_dir.path().join("content/git-docs").exists());
+}
+
+#[test]
+fn git_lfs_skip_is_recorded() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "v1\n")]);
+
+    env.cmd()
+        .args(["store", "add", "git-docs", "--lfs", "skip"])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    let index = fs::read_to_string(env.data_dir.path().join("index.toml")).unwrap();
+    assert!(index.contains("lfs = \"skip\""), "got: {index}");
+    env.cmd()
+        .args(["info", "git-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("LFS:         skip"));
+}
+
+#[test]
+fn git_lfs_options_rejected_for_local_sources() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+
+    env.cmd()
+        .args(["store", "add", "docs", "--lfs", "fetch"])
+        .arg(&sample)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only apply to git sources"));
+}
+
+#[test]
+fn git_failed_update_keeps_previous_content() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "v1\n")]);
+
+    env.cmd()
+        .args(["store", "add", "git-docs"])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    fs::remove_dir_all(&upstream).unwrap();
+    env.cmd()
+        .args(["store", "update", "git-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 failed"));
+
+    let readme = env.data_dir.path().join("content/git-docs/README.md");
+    assert_eq!(fs::read_to_string(readme).unwrap(), "v1\n");
+    let status = git(env.data_dir.path(), &["status", "--porcelain", "content"]);
+    assert!(status.is_empty(), "got: {status}");
+}
+
+fn mirror_count(env: &TestEnv) -> usize {
+    let cache = env.data_dir.path().join("cache/git");
+    fs::read_dir(cache).map(|d| d.count()).unwrap_or(0)
+}
+
+#[test]
+fn git_cache_mirror_is_fetched_incrementally() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "v1\n")]);
+
+    env.cmd()
+        .args(["store", "add", "git-docs"])
+        .arg(&upstream)
+        .assert()
+        .success();
+    assert_eq!(mirror_count(&env), 1);
+
+    let head = commit_files(&upstream, &[("README.md", "v2\n")], "bump");
+    env.cmd()
+        .args(["store", "update", "git-docs"])
+        .assert()
+        .success();
+
+    let readme = env.data_dir.path().join("content/git-docs/README.md");
+    assert_eq!(fs::read_to_string(readme).unwrap(), "v2\n");
+    assert!(!env.data_dir.path().join("content/git-docs/.git").exists());
+
+    let mirror = fs::read_dir(env.data_dir.path().join("cache/git"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    assert_eq!(git(&mirror, &["rev-parse", "main"]), head);
+
+    // The cache is local state, not part of the data dir's history
+    let status = git(env.data_dir.path(), &["status", "--porcelain"]);
+    assert!(!status.contains("cache"), "got: {status}");
+}
+
+#[test]
+fn git_cache_follows_a_moved_upstream_tag() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "v1\n")]);
+    git(&upstream, &["tag", "v1.0"]);
+
+    env.cmd()
+        .args(["store", "add", "git-docs"])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    // Re-tag the release on a new commit
+    let head = commit_files(&upstream, &[("README.md", "v2\n")], "fix release");
+    git(&upstream, &["tag", "-f", "v1.0"]);
+    env.cmd()
+        .args(["store", "update", "git-docs"])
+        .assert()
+        .success();
+
+    let readme = env.data_dir.path().join("content/git-docs/README.md");
+    assert_eq!(fs::read_to_string(readme).unwrap(), "v2\n");
+    let mirror = fs::read_dir(env.data_dir.path().join("cache/git"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    assert_eq!(git(&mirror, &["rev-parse", "v1.0^{commit}"]), head);
+}
+
+#[test]
+fn git_cache_shared_between_references_with_subpaths() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream(
+        "upstream",
+        &[("README.md", "root\n"), ("api/index.md", "api\n"), ("guide/intro.md", "guide\n")],
+    );
+
+    for (name, subpath) in [("api-docs", "api"), ("guide-docs", "guide")] {
+        env.cmd()
+            .args(["store", "add", name, "--subpath", subpath])
+            .arg(&upstream)
+            .assert()
+            .success();
+    }
+    assert_eq!(mirror_count(&env), 1);
+
+    let content = env.data_dir.path().join("content");
+    assert_eq!(fs::read_to_string(content.join("api-docs/index.md")).unwrap(), "api\n");
+    assert!(!content.join("api-docs/README.md").exists());
+    assert_eq!(fs::read_to_string(content.join("guide-docs/intro.md")).unwrap(), "guide\n");
+
+    env.cmd()
+        .args(["store", "update"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 updated"));
+}
+
+#[test]
+fn git_missing_subpath_fails() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "root\n")]);
+
+    env.cmd()
+        .args(["store", "add", "git-docs", "--subpath", "nope"])
+        .arg(&upstream)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("subpath 'nope' not found"));
+    assert!(!env.data_dir.path().join("content/git-docs").exists());
+}
+
+#[test]
+fn git_cache_disabled_clones_directly() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "v1\n")]);
+
+    env.cmd()
+        .args(["config", "set", "git_cache", "false"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["store", "add", "git-docs"])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    assert_eq!(mirror_count(&env), 0);
+    let readme = env.data_dir.path().join("content/git-docs/README.md");
+    assert_eq!(fs::read_to_string(readme).unwrap(), "v1\n");
+}
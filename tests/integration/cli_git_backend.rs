@@ -0,0 +1,169 @@
+use predicates::prelude::*;
+
+use crate::common::{TestEnv, git};
+
+fn use_in_process_backend(env: &TestEnv) {
+    env.cmd()
+        .args(["config", "set", "git_backend", "gix"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn config_shows_git_backend() {
+    let env = TestEnv::new();
+
+    env.cmd()
+        .args(["config", "get", "git_backend"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("auto"));
+
+    use_in_process_backend(&env);
+    env.cmd()
+        .args(["config", "show"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Git backend:    gix (in-process)"));
+
+    env.cmd()
+        .args(["config", "set", "git_backend", "libgit2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown git backend 'libgit2'"));
+}
+
+#[test]
+fn in_process_backend_commits_store_operations() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    use_in_process_backend(&env);
+
+    env.add_repo_ref("my-docs", &sample);
+    std::fs::write(sample.join("README.md"), "# Updated\n").unwrap();
+    std::fs::remove_file(sample.join("docs/notes.txt")).unwrap();
+    env.cmd()
+        .args(["store", "update", "my-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("README.md"))
+        .stdout(predicate::str::contains("docs/notes.txt"));
+    env.cmd()
+        .args(["store", "remove", "my-docs", "--force"])
+        .assert()
+        .success();
+
+    // The CLI sees the same history and a clean worktree
+    let log = git(env.data_dir.path(), &["log", "--format=%s"]);
+    assert!(
+        log.starts_with("Remove reference: my-docs\nUpdate reference: my-docs (1 modified, 1 removed)\nAdd"),
+        "{log}"
+    );
+    assert_eq!(git(env.data_dir.path(), &["status", "--porcelain"]), "");
+
+    env.cmd()
+        .args(["versions", "my-docs"])
+        .assert()
+        .failure();
+    env.cmd().args(["store", "undo"]).assert().success();
+    let content = env.data_dir.path().join("content/my-docs");
+    assert_eq!(std::fs::read_to_string(content.join("README.md")).unwrap(), "# Updated\n");
+    assert!(!content.join("docs/notes.txt").exists());
+    assert_eq!(git(env.data_dir.path(), &["status", "--porcelain"]), "");
+}
+
+#[test]
+fn in_process_backend_pins_releases() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    use_in_process_backend(&env);
+
+    env.add_repo_ref("my-docs", &sample);
+    env.cmd()
+        .args(["store", "release", "my-docs", "1.0.0"])
+        .assert()
+        .success();
+    std::fs::write(sample.join("README.md"), "# Updated\n").unwrap();
+    env.cmd()
+        .args(["store", "update", "my-docs"])
+        .assert()
+        .success();
+
+    env.cmd()
+        .args(["versions", "my-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1.0.0"))
+        .stdout(predicate::str::contains("Update reference: my-docs"));
+
+    env.init_project();
+    env.cmd()
+        .args(["add", "my-docs", "--pin", "^1"])
+        .assert()
+        .success();
+    env.cmd().args(["sync"]).assert().success();
+
+    let synced = env.project_dir.path().join(".references/my-docs/README.md");
+    assert_eq!(std::fs::read_to_string(synced).unwrap(), "# Sample Reference\n");
+}
+
+#[test]
+fn auto_backend_works_without_git_on_path() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+
+    env.cmd()
+        .env("PATH", "")
+        .args(["store", "add", "my-docs"])
+        .arg(&sample)
+        .assert()
+        .success();
+    env.cmd()
+        .env("PATH", "")
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("my-docs"));
+
+    let log = git(env.data_dir.path(), &["log", "--format=%s"]);
+    assert!(log.starts_with("Add reference: my-docs\n"), "{log}");
+}
+
+#[test]
+fn registry_submodule_settings_are_read_without_git_on_path() {
+    let env = TestEnv::new();
+    let reg_dir = env.create_fake_registry(&[("team-docs", "# Team\n")]);
+    let reg_url = format!("file://{}", reg_dir.display());
+    env.cmd()
+        .args(["registry", "add", "team", &reg_url])
+        .assert()
+        .success();
+
+    // Without config.toml the registry's URL comes from .gitmodules
+    std::fs::remove_file(env.data_dir.path().join("config.toml")).unwrap();
+    env.cmd()
+        .env("PATH", "")
+        .args(["registry", "reorder", "team", "local"])
+        .assert()
+        .success();
+
+    let config = std::fs::read_to_string(env.data_dir.path().join("config.toml")).unwrap();
+    assert!(config.contains(&format!("url = \"{reg_url}\"")), "{config}");
+}
+
+#[test]
+fn git_source_without_git_on_path_fails_up_front() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "# Upstream\n")]);
+
+    env.cmd()
+        .env("PATH", "")
+        .args(["store", "add", "upstream-docs", "--ref", "main"])
+        .arg(format!("file://{}", upstream.display()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "cannot fetch 'upstream-docs': git sources need the git command",
+        ));
+    assert!(!env.data_dir.path().join("content/upstream-docs").exists());
+}
@@ -0,0 +1,148 @@
+use predicates::prelude::*;
+
+use crate::common::{TestEnv, git};
+
+#[test]
+fn history_lists_store_operations() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+    env.create_bundle("stack", &["my-docs"]);
+
+    env.cmd()
+        .args(["store", "history"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("add    reference my-docs"))
+        .stdout(predicate::str::contains("add    bundle    stack"));
+
+    env.cmd()
+        .args(["store", "history", "-n", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bundle    stack"))
+        .stdout(predicate::str::contains("my-docs").not());
+}
+
+#[test]
+fn undo_removes_added_reference() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("keep", &sample);
+    env.add_repo_ref("oops", &sample);
+
+    env.cmd()
+        .args(["store", "undo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored store to"));
+
+    let content = env.data_dir.path().join("content");
+    assert!(content.join("keep/README.md").exists());
+    assert!(!content.join("oops").exists(), "undone content should be deleted");
+
+    env.cmd()
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("keep"))
+        .stdout(predicate::str::contains("oops").not());
+
+    env.cmd()
+        .args(["store", "history", "-n", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("undo   store"));
+}
+
+#[test]
+fn undo_twice_redoes() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+
+    env.cmd().args(["store", "undo"]).assert().success();
+    env.cmd()
+        .args(["info", "my-docs"])
+        .assert()
+        .failure();
+
+    env.cmd().args(["store", "undo"]).assert().success();
+    env.cmd().args(["info", "my-docs"]).assert().success();
+    assert!(env.data_dir.path().join("content/my-docs/src/lib.rs").exists());
+}
+
+#[test]
+fn undo_to_restores_earlier_content() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+    let before = git(env.data_dir.path(), &["rev-parse", "HEAD"]);
+
+    std::fs::write(sample.join("README.md"), "# Changed\n").unwrap();
+    std::fs::write(sample.join("extra.md"), "new file\n").unwrap();
+    env.cmd()
+        .args(["store", "update", "my-docs"])
+        .assert()
+        .success();
+    env.add_repo_ref("later", &sample);
+
+    env.cmd()
+        .args(["store", "undo", "--to", &before[..8]])
+        .assert()
+        .success();
+
+    let content = env.data_dir.path().join("content");
+    assert_eq!(
+        std::fs::read_to_string(content.join("my-docs/README.md")).unwrap(),
+        "# Sample Reference\n"
+    );
+    assert!(!content.join("my-docs/extra.md").exists());
+    assert!(!content.join("later").exists());
+}
+
+#[test]
+fn undo_unknown_revision_fails() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+
+    env.cmd()
+        .args(["store", "undo", "--to", "deadbeef"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown revision 'deadbeef'"));
+}
+
+#[test]
+fn undo_skips_registry_commits() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+    let reg_dir = env.create_fake_registry(&[("remote-ref", "# Remote\n")]);
+    env.cmd()
+        .args(["registry", "add", "team", &format!("file://{}", reg_dir.display())])
+        .assert()
+        .success();
+
+    // The last commit only added a registry; undo reverts the store add before it
+    env.cmd().args(["store", "undo"]).assert().success();
+    env.cmd().args(["info", "my-docs"]).assert().failure();
+    env.cmd().args(["info", "remote-ref"]).assert().success();
+}
+
+#[test]
+fn undo_to_current_state_is_refused() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+    let head = git(env.data_dir.path(), &["rev-parse", "HEAD"]);
+
+    env.cmd()
+        .args(["store", "undo", "--to", "HEAD"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("nothing to undo"))
+        .stderr(predicate::str::contains("git command failed").not());
+    assert_eq!(git(env.data_dir.path(), &["rev-parse", "HEAD"]), head);
+}
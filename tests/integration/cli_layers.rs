@@ -0,0 +1,200 @@
+use std::fs;
+use std::path::PathBuf;
+
+use predicates::prelude::*;
+
+use crate::common::TestEnv;
+
+/// Build a separate store under project_dir/<name> holding `refs` (name, README content).
+fn create_layer(env: &TestEnv, name: &str, refs: &[(&str, &str)]) -> PathBuf {
+    let layer = env.project_dir.path().join(name);
+    for (ref_name, content) in refs {
+        let src = env.project_dir.path().join(format!("{name}-{ref_name}-src"));
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("README.md"), content).unwrap();
+        env.cmd_with_data_dir(&layer)
+            .args(["store", "add", ref_name])
+            .arg(&src)
+            .assert()
+            .success();
+    }
+    layer
+}
+
+#[test]
+fn layer_from_env_is_listed_and_resolved() {
+    let env = TestEnv::new();
+    let layer = create_layer(&env, "team-store", &[("shared-docs", "# Shared\n")]);
+
+    env.cmd()
+        .env("REFSTORE_PATH", format!("team={}", layer.display()))
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("team: shared-docs"));
+
+    env.cmd()
+        .env("REFSTORE_PATH", format!("team={}", layer.display()))
+        .args(["info", "shared-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Layer:       team ({})",
+            layer.display()
+        )));
+}
+
+#[test]
+fn layer_bare_path_named_after_directory() {
+    let env = TestEnv::new();
+    let layer = create_layer(&env, "platform", &[("shared-docs", "# Shared\n")]);
+
+    env.cmd()
+        .env("REFSTORE_PATH", &layer)
+        .args(["info", "shared-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Layer:       platform"));
+}
+
+#[test]
+fn layer_precedence_follows_order() {
+    let env = TestEnv::new();
+    let team = create_layer(&env, "team", &[("docs", "team copy\n")]);
+    let system = create_layer(&env, "system", &[("docs", "system copy\n"), ("only-system", "x\n")]);
+    let path = std::env::join_paths([&team, &system]).unwrap();
+
+    env.cmd()
+        .env("REFSTORE_PATH", &path)
+        .args(["info", "docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Layer:       team"));
+
+    env.cmd()
+        .env("REFSTORE_PATH", &path)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("team: docs"))
+        .stdout(predicate::str::contains("system: only-system"))
+        .stdout(predicate::str::contains("system: docs").not());
+}
+
+#[test]
+fn layer_shadowed_by_local_reference() {
+    let env = TestEnv::new();
+    let layer = create_layer(&env, "team", &[("docs", "team copy\n")]);
+    let sample = env.create_sample_files();
+    env.add_repo_ref("docs", &sample);
+
+    env.cmd()
+        .env("REFSTORE_PATH", &layer)
+        .args(["info", "docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Registry:    local"));
+}
+
+#[test]
+fn layer_from_config_and_sync() {
+    let env = TestEnv::new();
+    let layer = create_layer(&env, "team", &[("shared-docs", "# Shared\n")]);
+    fs::write(
+        env.data_dir.path().join("config.toml"),
+        format!("[[layers]]\nname = \"team\"\npath = \"{}\"\n", layer.display()),
+    )
+    .unwrap();
+
+    env.cmd()
+        .args(["config", "show"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("team: {}", layer.display())));
+
+    env.init_project();
+    env.cmd()
+        .args(["add", "shared-docs", "--sync"])
+        .assert()
+        .success();
+
+    let synced = env.project_dir.path().join(".references/shared-docs/README.md");
+    assert_eq!(fs::read_to_string(synced).unwrap(), "# Shared\n");
+}
+
+#[test]
+fn layer_bundles_are_merged() {
+    let env = TestEnv::new();
+    let layer = create_layer(&env, "team", &[("shared-docs", "# Shared\n")]);
+    env.cmd_with_data_dir(&layer)
+        .args(["bundle", "create", "team-stack", "--ref", "shared-docs"])
+        .assert()
+        .success();
+
+    env.cmd()
+        .env("REFSTORE_PATH", &layer)
+        .args(["bundle", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("team-stack (1 refs)"));
+}
+
+#[test]
+fn layer_missing_path_is_skipped() {
+    let env = TestEnv::new();
+    let missing = env.project_dir.path().join("not-mounted");
+
+    env.cmd()
+        .env("REFSTORE_PATH", &missing)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No references in repository."));
+}
+
+#[test]
+fn layer_names_must_not_collide_with_registries() {
+    let env = TestEnv::new();
+    let reg_dir = env.create_fake_registry(&[("team-docs", "# Team\n")]);
+    env.cmd()
+        .args(["registry", "add", "team", &format!("file://{}", reg_dir.display())])
+        .assert()
+        .success();
+    let layer = create_layer(&env, "team-store", &[("shared-docs", "# Shared\n")]);
+
+    env.cmd()
+        .env("REFSTORE_PATH", format!("team={}", layer.display()))
+        .args(["list"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("a layer can't share its name with a remote registry"));
+
+    let path = std::env::join_paths([format!("platform={}", layer.display()), format!("platform={}", layer.display())])
+        .unwrap();
+    env.cmd()
+        .env("REFSTORE_PATH", path)
+        .args(["list"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("two layers can't share a name"));
+
+    env.cmd()
+        .env("REFSTORE_PATH", format!("platform={}", layer.display()))
+        .args(["registry", "add", "platform", &format!("file://{}", reg_dir.display())])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("a layer can't share its name with a remote registry"));
+}
+
+#[test]
+fn layer_path_containing_equals_sign_is_a_bare_path() {
+    let env = TestEnv::new();
+    let layer = create_layer(&env, "a=b/platform", &[("shared-docs", "# Shared\n")]);
+
+    env.cmd()
+        .env("REFSTORE_PATH", &layer)
+        .args(["info", "shared-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Layer:       platform"));
+}
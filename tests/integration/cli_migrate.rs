@@ -0,0 +1,122 @@
+use std::fs;
+
+use predicates::prelude::*;
+
+use crate::common::{TestEnv, git};
+
+#[test]
+fn migrate_new_index_written_at_current_version() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+
+    let index = fs::read_to_string(env.data_dir.path().join("index.toml")).unwrap();
+    assert!(index.starts_with("version = 1\n"), "got: {index}");
+}
+
+#[test]
+fn migrate_version_zero_index_is_upgraded_and_committed() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+
+    // Older releases stamped indexes with version 0
+    let index_path = env.data_dir.path().join("index.toml");
+    let index = fs::read_to_string(&index_path).unwrap();
+    fs::write(&index_path, index.replacen("version = 1", "version = 0", 1)).unwrap();
+    git(env.data_dir.path(), &["commit", "-am", "old format"]);
+
+    // Read-only commands upgrade in memory but leave the file alone
+    env.cmd()
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("my-docs"));
+    let subject = git(env.data_dir.path(), &["log", "-1", "--format=%s"]);
+    assert_eq!(subject, "old format");
+
+    env.cmd()
+        .args(["versions", "my-docs"])
+        .assert()
+        .success();
+
+    let index = fs::read_to_string(&index_path).unwrap();
+    assert!(index.starts_with("version = 1\n"), "got: {index}");
+    let subject = git(env.data_dir.path(), &["log", "-1", "--format=%s"]);
+    assert_eq!(subject, "Migrate index from format version 0 to 1");
+
+    // Already migrated: no further commits
+    env.cmd().args(["versions", "my-docs"]).assert().success();
+    let subject = git(env.data_dir.path(), &["log", "-1", "--format=%s"]);
+    assert_eq!(subject, "Migrate index from format version 0 to 1");
+}
+
+#[test]
+fn migrate_newer_index_is_refused() {
+    let env = TestEnv::new();
+    fs::write(
+        env.data_dir.path().join("index.toml"),
+        "version = 99\n\n[references.future]\nname = \"future\"\nnew_field = true\n",
+    )
+    .unwrap();
+
+    env.cmd()
+        .args(["list"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("index format version 99"))
+        .stderr(predicate::str::contains("upgrade refstore"));
+}
+
+#[test]
+fn migrate_newer_remote_index_is_refused() {
+    let env = TestEnv::new();
+    let reg_dir = env.create_fake_registry(&[("team-docs", "# Team\n")]);
+    env.cmd()
+        .args(["registry", "add", "team", &format!("file://{}", reg_dir.display())])
+        .assert()
+        .success();
+    fs::write(
+        env.data_dir.path().join("registries/team/index.toml"),
+        "version = 99\n\n[references.future]\nname = \"future\"\n",
+    )
+    .unwrap();
+
+    env.cmd()
+        .args(["list"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("team/index.toml uses index format version 99"))
+        .stderr(predicate::str::contains("upgrade refstore"));
+}
+
+#[test]
+fn migrate_newer_manifest_is_refused() {
+    let env = TestEnv::new();
+    env.init_project();
+    let manifest = env.project_dir.path().join("refstore.toml");
+    let content = fs::read_to_string(&manifest).unwrap();
+    fs::write(&manifest, content.replacen("version = 1", "version = 2", 1)).unwrap();
+
+    env.cmd()
+        .args(["status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("manifest format version 2"))
+        .stderr(predicate::str::contains("upgrade refstore"));
+}
+
+#[test]
+fn migrate_invalid_version_is_rejected() {
+    let env = TestEnv::new();
+    env.init_project();
+    let manifest = env.project_dir.path().join("refstore.toml");
+    let content = fs::read_to_string(&manifest).unwrap();
+    fs::write(&manifest, content.replacen("version = 1", "version = \"one\"", 1)).unwrap();
+
+    env.cmd()
+        .args(["status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid manifest format version"));
+}
@@ -0,0 +1,126 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use predicates::prelude::*;
+
+use crate::common::{TestEnv, git};
+
+fn inode(path: &Path) -> u64 {
+    fs::metadata(path).unwrap().ino()
+}
+
+fn enable_dedup(env: &TestEnv) {
+    env.cmd()
+        .args(["config", "set", "dedup_content", "true"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn objects_identical_files_are_hardlinked() {
+    let env = TestEnv::new();
+    enable_dedup(&env);
+    let sample = env.create_sample_files();
+    env.add_repo_ref("first", &sample);
+    env.add_repo_ref("second", &sample);
+
+    let content = env.data_dir.path().join("content");
+    let a = content.join("first/README.md");
+    let b = content.join("second/README.md");
+    assert_eq!(inode(&a), inode(&b));
+    assert_eq!(fs::read_to_string(&b).unwrap(), "# Sample Reference\n");
+    assert!(fs::metadata(&a).unwrap().nlink() >= 3, "both copies plus the object");
+
+    // The object store is local state, not part of the data dir's history
+    let status = git(env.data_dir.path(), &["status", "--porcelain"]);
+    assert!(!status.contains("objects"), "got: {status}");
+}
+
+#[test]
+fn objects_gc_links_existing_content_and_prunes() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("first", &sample);
+    env.add_repo_ref("second", &sample);
+
+    let content = env.data_dir.path().join("content");
+    let a = content.join("first/src/lib.rs");
+    let b = content.join("second/src/lib.rs");
+    assert_ne!(inode(&a), inode(&b), "dedup is off by default");
+
+    enable_dedup(&env);
+    env.cmd()
+        .args(["store", "gc"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Linked 10 files (5 newly deduplicated"));
+    assert_eq!(inode(&a), inode(&b));
+
+    // An update that changes a file leaves its old object unreferenced
+    fs::write(sample.join("src/lib.rs"), "pub fn changed() {}\n").unwrap();
+    env.cmd()
+        .args(["store", "update", "first"])
+        .assert()
+        .success();
+    assert_ne!(inode(&a), inode(&b));
+    assert_eq!(fs::read_to_string(&b).unwrap(), "pub fn hello() {}\n");
+
+    env.cmd()
+        .args(["store", "remove", "second", "--force"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["store", "gc"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pruned 1 unused objects."));
+}
+
+#[test]
+fn objects_gc_with_dedup_off_removes_objects() {
+    let env = TestEnv::new();
+    enable_dedup(&env);
+    let sample = env.create_sample_files();
+    env.add_repo_ref("first", &sample);
+
+    env.cmd()
+        .args(["config", "set", "dedup_content", "false"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["store", "gc"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pruned 5 unused objects."));
+
+    let readme = env.data_dir.path().join("content/first/README.md");
+    assert_eq!(fs::read_to_string(&readme).unwrap(), "# Sample Reference\n");
+    assert_eq!(fs::metadata(&readme).unwrap().nlink(), 1);
+}
+
+#[test]
+fn objects_snippet_edit_does_not_touch_shared_object() {
+    let env = TestEnv::new();
+    enable_dedup(&env);
+    for name in ["one", "two"] {
+        env.cmd()
+            .args(["store", "add-snippet", name, "--text", "same text\n"])
+            .assert()
+            .success();
+    }
+    // Snippet files have different names but identical content
+    env.cmd().args(["store", "gc"]).assert().success();
+
+    let content = env.data_dir.path().join("content");
+    assert_eq!(inode(&content.join("one/one.md")), inode(&content.join("two/two.md")));
+
+    env.cmd()
+        .args(["store", "edit-snippet", "one", "--text", "edited\n"])
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(content.join("one/one.md")).unwrap(), "edited\n");
+    assert_eq!(fs::read_to_string(content.join("two/two.md")).unwrap(), "same text\n");
+}
@@ -0,0 +1,146 @@
+use std::fs;
+
+use predicates::prelude::*;
+
+use crate::common::TestEnv;
+
+/// A reference with releases 1.0.0, 1.2.0, 1.4.0, and 2.0.0, whose README
+/// holds the version it was released as.
+fn released_reference(env: &TestEnv) {
+    let sample = env.create_sample_files();
+    for (i, version) in ["1.0.0", "1.2.0", "1.4.0", "2.0.0"].iter().enumerate() {
+        fs::write(sample.join("README.md"), format!("{version}\n")).unwrap();
+        if i == 0 {
+            env.add_repo_ref("my-docs", &sample);
+        } else {
+            env.cmd().args(["store", "update", "my-docs"]).assert().success();
+        }
+        env.cmd()
+            .args(["store", "release", "my-docs", version])
+            .assert()
+            .success();
+    }
+}
+
+/// Pin `my-docs` in the project manifest, replacing any earlier pin.
+fn pin(env: &TestEnv, version: &str) {
+    // Fails harmlessly when nothing is pinned yet
+    env.cmd().args(["remove", "my-docs"]).output().unwrap();
+    env.cmd()
+        .args(["add", "my-docs", "--pin", version])
+        .assert()
+        .success();
+}
+
+fn synced_readme(env: &TestEnv) -> String {
+    fs::read_to_string(env.project_dir.path().join(".references/my-docs/README.md")).unwrap()
+}
+
+#[test]
+fn range_pin_syncs_highest_match() {
+    let env = TestEnv::new();
+    released_reference(&env);
+    env.init_project();
+
+    pin(&env, "^1.2");
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("my-docs: synced (version: ^1.2 -> 1.4.0)"));
+    assert_eq!(synced_readme(&env), "1.4.0\n");
+
+    pin(&env, "~1.2.0");
+    env.cmd().args(["sync"]).assert().success();
+    assert_eq!(synced_readme(&env), "1.2.0\n");
+}
+
+#[test]
+fn range_pin_matches_semver_registry_tags() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+    env.cmd().args(["store", "tag", "v1.1.0"]).assert().success();
+    fs::write(sample.join("README.md"), "# Updated\n").unwrap();
+    env.cmd().args(["store", "update", "my-docs"]).assert().success();
+    env.cmd().args(["store", "tag", "v3.0.0"]).assert().success();
+
+    env.init_project();
+    pin(&env, "^1");
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("version: ^1 -> 1.1.0"));
+    assert_eq!(synced_readme(&env), "# Sample Reference\n");
+}
+
+#[test]
+fn range_pin_skips_registry_tags_before_the_reference_existed() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("older", &sample);
+    env.cmd().args(["store", "tag", "v1.0.0"]).assert().success();
+    env.add_repo_ref("my-docs", &sample);
+    env.cmd().args(["store", "tag", "v1.1.0"]).assert().success();
+
+    env.init_project();
+    pin(&env, "~1.0");
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("no release of 'my-docs' matches '~1.0'"));
+
+    pin(&env, "^1");
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("version: ^1 -> 1.1.0"));
+}
+
+#[test]
+fn range_pin_without_match_fails() {
+    let env = TestEnv::new();
+    released_reference(&env);
+    env.init_project();
+
+    pin(&env, "^3");
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("no release of 'my-docs' matches '^3'"))
+        .stdout(predicate::str::contains("1 failed"));
+}
+
+#[test]
+fn outdated_shows_compatible_and_incompatible_releases() {
+    let env = TestEnv::new();
+    released_reference(&env);
+    env.init_project();
+
+    pin(&env, "1.0.0");
+    env.cmd()
+        .args(["outdated"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "my-docs (1.0.0): using 1.0.0, compatible 1.4.0, latest 2.0.0 (incompatible)",
+        ));
+
+    pin(&env, "^1.2");
+    env.cmd()
+        .args(["outdated"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("my-docs (^1.2): using 1.4.0, latest 2.0.0 (incompatible)"));
+
+    pin(&env, "^2");
+    env.cmd()
+        .args(["outdated"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All pinned references are up to date."));
+}
@@ -0,0 +1,169 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+use crate::common::TestEnv;
+
+/// A `refstore-source-wiki` helper that writes one page from its arguments
+/// and reports `$WIKI_VERSION` as the version.
+const WIKI_HELPER: &str = r#"#!/bin/sh
+target=""
+args=""
+previous="(none)"
+read -r header
+[ "$header" = "refstore-source 1" ] || { echo "error bad header: $header"; exit 1; }
+while IFS= read -r line; do
+  [ -z "$line" ] && break
+  case "$line" in
+    "target "*) target="${line#target }" ;;
+    "arg "*) args="$args ${line#arg }" ;;
+    "previous "*) previous="${line#previous }" ;;
+  esac
+done
+if [ "$args" = " missing" ]; then
+  echo "error page not found"
+  exit 0
+fi
+printf '# Wiki:%s\nprevious: %s\n' "$args" "$previous" > "$target/page.md"
+echo "version $WIKI_VERSION"
+echo ok
+"#;
+
+/// Install the wiki helper into a bin dir and return it.
+fn install_helper(env: &TestEnv) -> PathBuf {
+    let bin = env.project_dir.path().join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let helper = bin.join("refstore-source-wiki");
+    fs::write(&helper, WIKI_HELPER).unwrap();
+    fs::set_permissions(&helper, fs::Permissions::from_mode(0o755)).unwrap();
+    bin
+}
+
+fn cmd_with_helper(env: &TestEnv, bin: &Path, version: &str) -> Command {
+    let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap_or_default());
+    let mut cmd = env.cmd();
+    cmd.env("PATH", path).env("WIKI_VERSION", version);
+    cmd
+}
+
+#[test]
+fn plugin_helper_fetches_content() {
+    let env = TestEnv::new();
+    let bin = install_helper(&env);
+
+    cmd_with_helper(&env, &bin, "rev-1")
+        .args(["store", "add", "onboarding", "wiki::ENG/Onboarding", "--plugin-arg", "--flat"])
+        .assert()
+        .success();
+
+    let page = env.data_dir.path().join("content/onboarding/page.md");
+    assert_eq!(
+        fs::read_to_string(page).unwrap(),
+        "# Wiki: ENG/Onboarding --flat\nprevious: (none)\n"
+    );
+
+    env.cmd()
+        .args(["info", "onboarding"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Source:      wiki::ENG/Onboarding --flat"))
+        .stdout(predicate::str::contains("Checksum:    rev-1"));
+}
+
+#[test]
+fn plugin_target_is_absolute_with_a_relative_data_dir() {
+    let env = TestEnv::new();
+    let bin = install_helper(&env);
+    let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap_or_default());
+
+    env.cmd_with_data_dir(Path::new("data"))
+        .env("PATH", path)
+        .env("WIKI_VERSION", "rev-1")
+        .args(["store", "add", "demo", "wiki::x"])
+        .assert()
+        .success();
+
+    let page = env.project_dir.path().join("data/content/demo/page.md");
+    assert_eq!(fs::read_to_string(page).unwrap(), "# Wiki: x\nprevious: (none)\n");
+}
+
+#[test]
+fn plugin_update_passes_previous_version() {
+    let env = TestEnv::new();
+    let bin = install_helper(&env);
+
+    cmd_with_helper(&env, &bin, "rev-1")
+        .args(["store", "add", "onboarding", "wiki::ENG/Onboarding"])
+        .assert()
+        .success();
+
+    cmd_with_helper(&env, &bin, "rev-2")
+        .args(["store", "update", "onboarding"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rev-1..rev-2"));
+
+    let page = env.data_dir.path().join("content/onboarding/page.md");
+    assert!(fs::read_to_string(page).unwrap().contains("previous: rev-1"));
+}
+
+#[test]
+fn plugin_missing_helper_fails() {
+    let env = TestEnv::new();
+
+    env.cmd()
+        .args(["store", "add", "docs", "nosuch::anything"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "source helper 'refstore-source-nosuch' failed: not found on PATH",
+        ));
+}
+
+#[test]
+fn plugin_helper_error_is_reported() {
+    let env = TestEnv::new();
+    let bin = install_helper(&env);
+
+    cmd_with_helper(&env, &bin, "rev-1")
+        .args(["store", "add", "docs", "wiki::missing"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("page not found"));
+
+    assert!(!env.data_dir.path().join("content/docs").exists());
+}
+
+#[test]
+fn plugin_rejects_git_options() {
+    let env = TestEnv::new();
+
+    env.cmd()
+        .args(["store", "add", "docs", "wiki::page", "--ref", "main"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only apply to git sources"));
+}
+
+#[test]
+fn plugin_rejects_values_that_would_inject_lines() {
+    let env = TestEnv::new();
+    let bin = install_helper(&env);
+    let elsewhere = env.project_dir.path().join("elsewhere");
+    fs::create_dir_all(&elsewhere).unwrap();
+
+    cmd_with_helper(&env, &bin, "rev-1")
+        .args(["store", "add", "docs", "wiki::page", "--plugin-arg"])
+        .arg(format!("x\ntarget {}", elsewhere.display()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("contains a control character"));
+
+    assert!(!elsewhere.join("page.md").exists());
+    assert!(!env.data_dir.path().join("content/docs").exists());
+}
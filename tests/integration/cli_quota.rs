@@ -0,0 +1,192 @@
+use std::fs;
+
+use predicates::prelude::*;
+
+use crate::common::TestEnv;
+
+fn set(env: &TestEnv, key: &str, value: &str) {
+    env.cmd().args(["config", "set", key, value]).assert().success();
+}
+
+#[test]
+fn quota_max_file_size_blocks_add() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    fs::write(sample.join("docs/big.txt"), "x".repeat(3000)).unwrap();
+    set(&env, "max_file_size", "1K");
+
+    env.cmd()
+        .args(["store", "add", "my-docs"])
+        .arg(&sample)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'my-docs' exceeds the store's size limits"))
+        .stderr(predicate::str::contains("1 file(s) exceed max_file_size 1.0 KiB"))
+        .stderr(predicate::str::contains("docs/big.txt (2.9 KiB)"))
+        .stderr(predicate::str::contains("--force"));
+    assert!(!env.data_dir.path().join("content/my-docs").exists());
+
+    env.cmd()
+        .args(["store", "add", "my-docs", "--force"])
+        .arg(&sample)
+        .assert()
+        .success();
+    assert!(env.data_dir.path().join("content/my-docs/docs/big.txt").exists());
+}
+
+#[test]
+fn quota_max_reference_size_blocks_add() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    for i in 0..3 {
+        fs::write(sample.join(format!("docs/part{i}.txt")), "x".repeat(600)).unwrap();
+    }
+    set(&env, "max_reference_size", "1K");
+
+    env.cmd()
+        .args(["store", "add", "my-docs"])
+        .arg(&sample)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exceeds max_reference_size 1.0 KiB"));
+}
+
+#[test]
+fn quota_blocks_update_and_keeps_previous_content() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+    set(&env, "max_file_size", "100");
+
+    fs::write(sample.join("README.md"), "x".repeat(500)).unwrap();
+    env.cmd()
+        .args(["store", "update", "my-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("README.md (500 B)"))
+        .stdout(predicate::str::contains("1 failed"));
+
+    let readme = env.data_dir.path().join("content/my-docs/README.md");
+    assert_eq!(fs::read_to_string(&readme).unwrap(), "# Sample Reference\n");
+
+    env.cmd()
+        .args(["store", "update", "my-docs", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 updated"));
+    assert_eq!(fs::read_to_string(&readme).unwrap().len(), 500);
+}
+
+#[test]
+fn quota_applies_to_git_sources() {
+    let env = TestEnv::new();
+    let big = "x".repeat(4096);
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "v1\n"), ("assets/dump.sql", &big)]);
+    set(&env, "max_file_size", "2K");
+
+    env.cmd()
+        .args(["store", "add", "git-docs"])
+        .arg(&upstream)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("assets/dump.sql (4.0 KiB)"));
+    assert!(!env.data_dir.path().join("content/git-docs").exists());
+}
+
+#[test]
+fn quota_binary_files_skipped() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    fs::write(sample.join("docs/diagram.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+    set(&env, "binary_files", "skip");
+
+    env.cmd()
+        .args(["store", "add", "my-docs"])
+        .arg(&sample)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("skipped binary file: docs/diagram.png"));
+
+    let content = env.data_dir.path().join("content/my-docs");
+    assert!(!content.join("docs/diagram.png").exists());
+    assert!(content.join("docs/guide.md").exists());
+}
+
+#[test]
+fn quota_skipping_every_file_is_refused() {
+    let env = TestEnv::new();
+    let images = env.project_dir.path().join("images");
+    fs::create_dir_all(&images).unwrap();
+    fs::write(images.join("diagram.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+    let upstream = env.create_git_upstream("upstream", &[("logo.bin", "\0\x01\x02")]);
+    set(&env, "binary_files", "skip");
+
+    for (name, source) in [("images", &images), ("git-images", &upstream)] {
+        env.cmd()
+            .args(["store", "add", name])
+            .arg(source)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(format!("nothing left to store in '{name}'")));
+        assert!(!env.data_dir.path().join("content").join(name).exists());
+    }
+
+    env.cmd()
+        .args(["store", "add", "images", "--force"])
+        .arg(&images)
+        .assert()
+        .success();
+    assert!(env.data_dir.path().join("content/images/diagram.png").exists());
+}
+
+#[test]
+fn quota_binary_files_warned() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    fs::write(sample.join("docs/diagram.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+    set(&env, "binary_files", "warn");
+
+    env.cmd()
+        .args(["store", "add", "my-docs"])
+        .arg(&sample)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("warning: stored binary file: docs/diagram.png"));
+    assert!(env.data_dir.path().join("content/my-docs/docs/diagram.png").exists());
+}
+
+#[test]
+fn quota_config_values() {
+    let env = TestEnv::new();
+    set(&env, "max_reference_size", "20M");
+
+    env.cmd()
+        .args(["config", "get", "max_reference_size"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("20.0 MiB"));
+    env.cmd()
+        .args(["config", "show"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Max file size:  none"))
+        .stdout(predicate::str::contains("Binary files:   allow"));
+
+    env.cmd()
+        .args(["config", "set", "max_file_size", "lots"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid size 'lots'"));
+    env.cmd()
+        .args(["config", "set", "binary_files", "maybe"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected allow, warn, or skip"));
+
+    set(&env, "max_reference_size", "none");
+    env.cmd()
+        .args(["config", "get", "max_reference_size"])
+        .assert()
+        .success()
+        .stdout("none\n");
+}
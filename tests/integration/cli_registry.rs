@@ -1,7 +1,7 @@
 use predicates::prelude::*;
 use std::fs;
 
-use crate::common::TestEnv;
+use crate::common::{TestEnv, git};
 
 #[test]
 fn registry_add_and_list() {
@@ -82,6 +82,165 @@ fn local_reference_wins_over_remote() {
     assert!(synced.exists(), "local reference should win over remote");
 }
 
+/// A local `shared` reference shadowing the `shared` of registry `team`.
+fn shadowed_env() -> TestEnv {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    let reg_dir = env.create_fake_registry(&[("shared", "# Remote version\n")]);
+    env.add_repo_ref("shared", &sample);
+    env.cmd()
+        .args(["registry", "add", "team", &format!("file://{}", reg_dir.display())])
+        .assert()
+        .success();
+    env
+}
+
+#[test]
+fn qualified_names_pick_a_registry() {
+    let env = shadowed_env();
+
+    env.cmd()
+        .args(["info", "team/shared"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Registry:    team"));
+    env.cmd()
+        .args(["info", "other/shared"])
+        .assert()
+        .failure();
+
+    env.cmd()
+        .args(["search", "remote version", "--ref", "team/shared"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("README.md"));
+    env.cmd()
+        .args(["search", "remote version", "--ref", "shared"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("README.md").not());
+}
+
+#[test]
+fn project_can_take_a_shadowed_copy() {
+    let env = shadowed_env();
+    env.init_project();
+
+    env.cmd()
+        .args(["add", "team/shared", "--sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("shared: synced"));
+
+    let manifest = fs::read_to_string(env.project_dir.path().join("refstore.toml")).unwrap();
+    assert!(manifest.contains("[references.shared]"), "{manifest}");
+    assert!(manifest.contains("registry = \"team\""), "{manifest}");
+    let synced = env.project_dir.path().join(".references/shared");
+    assert_eq!(fs::read_to_string(synced.join("README.md")).unwrap(), "# Remote version\n");
+    assert!(!synced.join("src").exists());
+
+    env.cmd()
+        .args(["remove", "team/shared"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn bundles_can_list_qualified_references() {
+    let env = shadowed_env();
+    env.create_bundle("stack", &["team/shared"]);
+    env.init_project();
+
+    env.cmd()
+        .args(["add", "stack", "--bundle"])
+        .assert()
+        .success();
+    env.cmd().args(["sync"]).assert().success();
+
+    let synced = env.project_dir.path().join(".references/shared");
+    assert_eq!(fs::read_to_string(synced.join("README.md")).unwrap(), "# Remote version\n");
+}
+
+#[test]
+fn list_shadowed_follows_registry_order() {
+    let env = shadowed_env();
+
+    env.cmd()
+        .args(["list", "--shadowed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("shared: local wins over team"));
+
+    env.cmd()
+        .args(["registry", "reorder", "team", "local"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("team, local"));
+    let config = fs::read_to_string(env.data_dir.path().join("config.toml")).unwrap();
+    assert!(config.contains("priority = 1"), "{config}");
+
+    env.cmd()
+        .args(["list", "--shadowed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("shared: team wins over local"));
+    env.cmd()
+        .args(["info", "shared"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Registry:    team"));
+    env.cmd()
+        .args(["registry", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("team: 1 references, 0 bundles, priority 1"));
+
+    // Back to the default order
+    env.cmd()
+        .args(["registry", "reorder", "team"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["list", "--shadowed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("shared: local wins over team"));
+}
+
+#[test]
+fn list_shadowed_without_conflicts() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+
+    env.cmd()
+        .args(["list", "--shadowed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No reference is defined in more than one registry."));
+}
+
+#[test]
+fn registry_reorder_rejects_incomplete_orders() {
+    let env = shadowed_env();
+
+    env.cmd()
+        .args(["registry", "reorder", "local"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'team' is missing"));
+    env.cmd()
+        .args(["registry", "reorder", "team", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("registry 'nope' not found"));
+    env.cmd()
+        .args(["registry", "reorder", "team", "team"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'team' is listed twice"));
+}
+
 #[test]
 fn sync_remote_registry_reference() {
     let env = TestEnv::new();
@@ -463,6 +622,138 @@ fn registry_update_pulls_new_content() {
         .assert()
         .success()
         .stdout(predicate::str::contains("updatable-reg: 2 references"));
+
+    // The cached index was refreshed by the update
+    assert!(env.data_dir.path().join("cache/index.json").exists());
+    env.cmd()
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("new-ref"));
+}
+
+// --- registry pins ---
+
+/// Commit one more reference to a fake registry.
+fn publish_registry_ref(reg_dir: &std::path::Path, name: &str) {
+    fs::create_dir_all(reg_dir.join("content").join(name)).unwrap();
+    fs::write(reg_dir.join("content").join(name).join("README.md"), format!("# {name}\n")).unwrap();
+    let index = fs::read_to_string(reg_dir.join("index.toml")).unwrap();
+    fs::write(
+        reg_dir.join("index.toml"),
+        format!(
+            "{index}\n[references.{name}]\nname = \"{name}\"\nkind = \"directory\"\nadded_at = \"2026-01-01T00:00:00Z\"\n\n[references.{name}.source]\ntype = \"local\"\npath = \"/fake\"\n"
+        ),
+    )
+    .unwrap();
+    git(reg_dir, &["add", "."]);
+    git(reg_dir, &["commit", "-m", &format!("add {name}")]);
+}
+
+#[test]
+fn registry_add_with_ref_stays_pinned_across_updates() {
+    let env = TestEnv::new();
+    let reg_dir = env.create_fake_registry(&[("first", "# First\n")]);
+    git(&reg_dir, &["tag", "v1"]);
+    publish_registry_ref(&reg_dir, "second");
+    git(&reg_dir, &["tag", "v2"]);
+    publish_registry_ref(&reg_dir, "third");
+    let reg_url = format!("file://{}", reg_dir.display());
+
+    env.cmd()
+        .args(["registry", "add", "team", &reg_url, "--ref", "v1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pinned to v1"));
+
+    for update in [&["registry", "update", "team"][..], &["registry", "update"]] {
+        env.cmd().args(update).assert().success();
+        env.cmd()
+            .args(["list"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("first"))
+            .stdout(predicate::str::contains("second").not());
+    }
+
+    env.cmd()
+        .args(["registry", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("team: 1 references, 0 bundles, pinned to v1"))
+        .stdout(predicate::str::contains("newer: v2"));
+
+    let gitmodules = fs::read_to_string(env.data_dir.path().join(".gitmodules")).unwrap();
+    assert!(gitmodules.contains("ref = v1"), "{gitmodules}");
+
+    // config.toml isn't committed; a data dir without it keeps the pin
+    fs::remove_file(env.data_dir.path().join("config.toml")).unwrap();
+    env.cmd().args(["registry", "update", "team"]).assert().success();
+    env.cmd()
+        .args(["registry", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("team: 1 references, 0 bundles, pinned to v1"));
+}
+
+#[test]
+fn registry_commands_reject_paths_as_names() {
+    let env = TestEnv::new();
+    for args in [&["registry", "pin", "../..", "v1"][..], &["registry", "update", ".."], &["registry", "remove", ".."]] {
+        env.cmd()
+            .args(args)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("invalid"));
+    }
+}
+
+#[test]
+fn registry_pin_moves_a_registry() {
+    let env = TestEnv::new();
+    let reg_dir = env.create_fake_registry(&[("first", "# First\n")]);
+    git(&reg_dir, &["tag", "v1"]);
+    let reg_url = format!("file://{}", reg_dir.display());
+    env.cmd()
+        .args(["registry", "add", "team", &reg_url])
+        .assert()
+        .success();
+
+    publish_registry_ref(&reg_dir, "second");
+    git(&reg_dir, &["tag", "v2"]);
+    publish_registry_ref(&reg_dir, "third");
+
+    // Pinning fetches, so tags published after `registry add` resolve
+    env.cmd()
+        .args(["registry", "pin", "team", "v2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pinned registry 'team' to v2"));
+    env.cmd()
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("second"))
+        .stdout(predicate::str::contains("third").not());
+    let subject = git(env.data_dir.path(), &["log", "-1", "--format=%s"]);
+    assert_eq!(subject, "Pin registry: team to v2");
+
+    env.cmd()
+        .args(["registry", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pinned to v2"))
+        .stdout(predicate::str::contains("newer").not());
+
+    env.cmd()
+        .args(["registry", "pin", "team", "v9"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown revision 'v9'"));
+    env.cmd()
+        .args(["registry", "pin", "missing", "v1"])
+        .assert()
+        .failure();
 }
 
 // --- full round-trip workflow ---
@@ -0,0 +1,115 @@
+use std::fs;
+
+use predicates::prelude::*;
+
+use crate::common::TestEnv;
+
+#[test]
+fn release_is_listed_by_versions_not_tags() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+
+    env.cmd()
+        .args(["store", "release", "my-docs", "1.2.0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Released 'my-docs' 1.2.0 (tag my-docs/v1.2.0)"));
+
+    fs::write(sample.join("README.md"), "# Updated\n").unwrap();
+    env.cmd().args(["store", "update", "my-docs"]).assert().success();
+    env.cmd()
+        .args(["store", "release", "my-docs", "v1.10.0", "-m", "Updated readme"])
+        .assert()
+        .success();
+
+    let output = env.cmd().args(["versions", "my-docs"]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let newer = stdout.find("  1.10.0").expect("1.10.0 listed");
+    let older = stdout.find("  1.2.0").expect("1.2.0 listed");
+    assert!(newer < older, "releases should be newest version first: {stdout}");
+
+    env.cmd()
+        .args(["store", "tags"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("my-docs/v").not());
+}
+
+#[test]
+fn release_pin_syncs_released_content() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+    env.cmd()
+        .args(["store", "release", "my-docs", "1.0.0"])
+        .assert()
+        .success();
+
+    fs::write(sample.join("README.md"), "# Updated\n").unwrap();
+    env.cmd().args(["store", "update", "my-docs"]).assert().success();
+
+    env.init_project();
+    env.cmd()
+        .args(["add", "my-docs", "--pin", "1.0.0"])
+        .assert()
+        .success();
+    env.cmd().args(["sync"]).assert().success();
+
+    let synced = env.project_dir.path().join(".references/my-docs/README.md");
+    assert_eq!(fs::read_to_string(synced).unwrap(), "# Sample Reference\n");
+}
+
+#[test]
+fn release_of_one_reference_does_not_version_another() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("released", &sample);
+    env.add_repo_ref("unreleased", &sample);
+    env.cmd()
+        .args(["store", "release", "released", "1.0.0"])
+        .assert()
+        .success();
+
+    env.init_project();
+    env.cmd()
+        .args(["add", "unreleased", "--pin", "1.0.0"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "version '1.0.0' of 'unreleased' not found",
+        ));
+}
+
+#[test]
+fn release_rejects_duplicates_and_invalid_versions() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+
+    env.cmd()
+        .args(["store", "release", "my-docs", "1.0.0"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["store", "release", "my-docs", "v1.0.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("release 1.0.0 of 'my-docs' already exists"));
+
+    env.cmd()
+        .args(["store", "release", "my-docs", "1.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid version '1.0'"));
+
+    env.cmd()
+        .args(["store", "release", "missing", "1.0.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found"));
+}
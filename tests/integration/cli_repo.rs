@@ -1,7 +1,7 @@
 use predicates::prelude::*;
 use std::fs;
 
-use crate::common::TestEnv;
+use crate::common::{TestEnv, commit_files, git};
 
 #[test]
 fn repo_add_local_dir() {
@@ -182,6 +182,52 @@ fn repo_info_not_found() {
         .failure();
 }
 
+#[test]
+fn read_commands_leave_data_dir_untouched() {
+    let env = TestEnv::new();
+
+    env.cmd().args(["list"]).assert().success();
+    env.cmd().args(["search", "anything"]).assert().success();
+    env.cmd().args(["info", "nonexistent"]).assert().failure();
+
+    let entries: Vec<_> = fs::read_dir(env.data_dir.path()).unwrap().collect();
+    assert!(entries.is_empty(), "read commands should not create anything: {entries:?}");
+}
+
+#[test]
+fn read_commands_do_not_run_git() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref_with_meta("my-docs", &sample, "Docs", &["rust"]);
+    env.cmd()
+        .args(["config", "set", "git_backend", "cli"])
+        .assert()
+        .success();
+    let head = git(env.data_dir.path(), &["rev-parse", "HEAD"]);
+
+    // With `git` off PATH, anything that shells out to it fails
+    env.cmd()
+        .env("PATH", "")
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("my-docs"));
+    env.cmd()
+        .env("PATH", "")
+        .args(["search", "Guide"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("guide.md"));
+    env.cmd()
+        .env("PATH", "")
+        .args(["info", "my-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Docs"));
+
+    assert_eq!(git(env.data_dir.path(), &["rev-parse", "HEAD"]), head);
+}
+
 #[test]
 fn repo_remove_force() {
     let env = TestEnv::new();
@@ -238,3 +284,79 @@ fn repo_update_refetches() {
     let content = fs::read_to_string(cached).unwrap();
     assert_eq!(content, "# Updated\n");
 }
+
+#[test]
+fn repo_update_prints_file_summary() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+
+    fs::write(sample.join("README.md"), "# Changed\n").unwrap();
+    fs::write(sample.join("docs/new.md"), "one\ntwo\n").unwrap();
+    fs::remove_file(sample.join("docs/notes.txt")).unwrap();
+
+    env.cmd()
+        .args(["store", "update", "my-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("done (1 added, 1 modified, 1 removed)"))
+        .stdout(predicate::str::contains("M README.md (+1 -1)"))
+        .stdout(predicate::str::contains("A docs/new.md (+2 -0)"))
+        .stdout(predicate::str::contains("D docs/notes.txt (+0 -1)"));
+
+    // The summary is recorded in the registry history
+    env.cmd()
+        .args(["versions", "my-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Update reference: my-docs (1 added, 1 modified, 1 removed)",
+        ));
+    let body = git(env.data_dir.path(), &["log", "-1", "--format=%b"]);
+    assert!(body.contains("M README.md (+1 -1)"), "commit body: {body}");
+}
+
+#[test]
+fn repo_update_without_changes() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+
+    env.cmd()
+        .args(["store", "update", "my-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("done (no changes)"));
+}
+
+#[test]
+fn repo_update_git_shows_upstream_range() {
+    let env = TestEnv::new();
+    let upstream = env.create_git_upstream("upstream", &[("README.md", "v1\n")]);
+    let first = git(&upstream, &["rev-parse", "HEAD"]);
+
+    env.cmd()
+        .args(["store", "add", "git-docs"])
+        .arg(&upstream)
+        .assert()
+        .success();
+
+    let second = commit_files(&upstream, &[("README.md", "v2\n")], "bump");
+
+    env.cmd()
+        .args(["store", "update", "git-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "upstream: {}..{}",
+            &first[..8],
+            &second[..8]
+        )))
+        .stdout(predicate::str::contains("M README.md (+1 -1)"));
+
+    env.cmd()
+        .args(["info", "git-docs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Checksum:    {second}")));
+}
@@ -0,0 +1,198 @@
+use std::fs;
+
+use predicates::prelude::*;
+
+use crate::common::TestEnv;
+
+#[test]
+fn snippet_add_with_text() {
+    let env = TestEnv::new();
+
+    env.cmd()
+        .args(["store", "add-snippet", "conventions", "--text", "Use snake_case.\n"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added snippet 'conventions'"));
+
+    let stored = env.data_dir.path().join("content/conventions/conventions.md");
+    assert_eq!(fs::read_to_string(stored).unwrap(), "Use snake_case.\n");
+
+    env.cmd()
+        .args(["list", "--kind", "snippet"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("conventions (snippet)"));
+
+    env.cmd()
+        .args(["info", "conventions"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(inline snippet)"));
+}
+
+#[test]
+fn snippet_add_from_stdin() {
+    let env = TestEnv::new();
+
+    env.cmd()
+        .args(["store", "add-snippet", "stdin-snip", "--from-stdin"])
+        .write_stdin("Piped text\n")
+        .assert()
+        .success();
+
+    let stored = env.data_dir.path().join("content/stdin-snip/stdin-snip.md");
+    assert_eq!(fs::read_to_string(stored).unwrap(), "Piped text\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn snippet_add_with_editor() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let env = TestEnv::new();
+    let editor = env.project_dir.path().join("fake-editor.sh");
+    fs::write(&editor, "#!/bin/sh\necho 'Written in editor' > \"$1\"\n").unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+
+    env.cmd()
+        .args(["store", "add-snippet", "edited"])
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .assert()
+        .success();
+
+    let stored = env.data_dir.path().join("content/edited/edited.md");
+    assert_eq!(fs::read_to_string(stored).unwrap(), "Written in editor\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn snippet_editor_file_does_not_use_the_name() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let env = TestEnv::new();
+    let editor = env.project_dir.path().join("fake-editor.sh");
+    let seen = env.project_dir.path().join("seen-path");
+    let script = format!("#!/bin/sh\necho \"$1\" > '{}'\necho text > \"$1\"\n", seen.display());
+    fs::write(&editor, script).unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+
+    env.cmd()
+        .args(["store", "add-snippet", "../../escape"])
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .assert()
+        .failure();
+
+    let seen = fs::read_to_string(seen).unwrap();
+    let file_name = std::path::Path::new(seen.trim()).file_name().unwrap().to_string_lossy().into_owned();
+    assert!(file_name.starts_with("refstore-snippet-"), "{seen}");
+    assert!(!seen.contains("escape"), "{seen}");
+}
+
+#[test]
+fn snippet_empty_text_rejected() {
+    let env = TestEnv::new();
+
+    env.cmd()
+        .args(["store", "add-snippet", "empty", "--text", "  \n"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("snippet text is empty"));
+}
+
+#[test]
+fn snippet_edit_creates_version() {
+    let env = TestEnv::new();
+
+    env.cmd()
+        .args(["store", "add-snippet", "conventions", "--text", "v1\n"])
+        .assert()
+        .success();
+
+    env.cmd()
+        .args(["store", "edit-snippet", "conventions", "--text", "v2\n"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updated snippet 'conventions'"));
+
+    let stored = env.data_dir.path().join("content/conventions/conventions.md");
+    assert_eq!(fs::read_to_string(stored).unwrap(), "v2\n");
+
+    env.cmd()
+        .args(["versions", "conventions"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Update reference: conventions"));
+}
+
+#[test]
+fn snippet_edit_non_snippet_fails() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+
+    env.cmd()
+        .args(["store", "edit-snippet", "my-docs", "--text", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a snippet"));
+}
+
+#[test]
+fn snippet_syncs_as_markdown_file() {
+    let env = TestEnv::new();
+
+    env.cmd()
+        .args(["store", "add-snippet", "conventions", "--text", "Use snake_case.\n"])
+        .assert()
+        .success();
+
+    env.init_project();
+    env.cmd()
+        .args(["add", "conventions", "--sync"])
+        .assert()
+        .success();
+
+    let synced = env.project_dir.path().join(".references/conventions.md");
+    assert_eq!(fs::read_to_string(&synced).unwrap(), "Use snake_case.\n");
+
+    // Re-sync after an edit replaces the file
+    env.cmd()
+        .args(["store", "edit-snippet", "conventions", "--text", "Use kebab-case.\n"])
+        .assert()
+        .success();
+    env.cmd().args(["sync"]).assert().success();
+    assert_eq!(fs::read_to_string(&synced).unwrap(), "Use kebab-case.\n");
+
+    env.cmd()
+        .args(["remove", "conventions", "--purge"])
+        .assert()
+        .success();
+    assert!(!synced.exists(), "purge should delete the snippet file");
+}
+
+#[test]
+fn snippet_skipped_by_store_update() {
+    let env = TestEnv::new();
+
+    env.cmd()
+        .args(["store", "add-snippet", "conventions", "--text", "keep me\n"])
+        .assert()
+        .success();
+
+    env.cmd()
+        .args(["store", "update"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No references in repository."));
+
+    env.cmd()
+        .args(["store", "update", "conventions"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("snippets have no source to fetch"));
+
+    let stored = env.data_dir.path().join("content/conventions/conventions.md");
+    assert_eq!(fs::read_to_string(stored).unwrap(), "keep me\n");
+}
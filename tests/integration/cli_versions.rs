@@ -1,6 +1,6 @@
 use predicates::prelude::*;
 
-use crate::common::TestEnv;
+use crate::common::{TestEnv, commit_files, git};
 
 #[test]
 fn versions_shows_history() {
@@ -199,3 +199,274 @@ fn repo_tags_empty() {
         .success()
         .stdout(predicate::str::contains("No tags"));
 }
+
+/// A remote registry "team" whose `remote-ref` is "# v1\n" at tag v1.0.0 and
+/// release remote-ref/v1.0.0, then "# v2\n" at tag v2.0.0.
+fn add_versioned_remote(env: &TestEnv) {
+    let reg_dir = env.create_fake_registry(&[("remote-ref", "# v1\n")]);
+    git(&reg_dir, &["tag", "v1.0.0"]);
+    git(&reg_dir, &["tag", "-a", "remote-ref/v1.0.0", "-m", "first release"]);
+    commit_files(&reg_dir, &[("content/remote-ref/README.md", "# v2\n")], "Update reference: remote-ref");
+    git(&reg_dir, &["tag", "v2.0.0"]);
+
+    env.cmd()
+        .args(["registry", "add", "team", &format!("file://{}", reg_dir.display())])
+        .assert()
+        .success();
+}
+
+#[test]
+fn versions_of_remote_reference_use_its_registry() {
+    let env = TestEnv::new();
+    add_versioned_remote(&env);
+
+    env.cmd()
+        .args(["versions", "remote-ref"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Update reference: remote-ref"))
+        .stdout(predicate::str::contains("  1.0.0"))
+        .stdout(predicate::str::contains("Registry tags (team): "))
+        .stdout(predicate::str::contains("v2.0.0"));
+}
+
+#[test]
+fn sync_remote_reference_pinned_to_its_registry_tag() {
+    let env = TestEnv::new();
+    add_versioned_remote(&env);
+    env.init_project();
+    let readme = env.project_dir.path().join(".references/remote-ref/README.md");
+
+    env.cmd()
+        .args(["add", "remote-ref", "--pin", "v2.0.0"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 synced, 0 failed"));
+    assert_eq!(std::fs::read_to_string(&readme).unwrap(), "# v2\n");
+
+    // Ranges resolve against the remote registry's releases too
+    env.cmd().args(["remove", "remote-ref"]).assert().success();
+    env.cmd()
+        .args(["add", "remote-ref", "--pin", "^1"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("version: ^1 -> 1.0.0"));
+    assert_eq!(std::fs::read_to_string(&readme).unwrap(), "# v1\n");
+}
+
+#[test]
+fn pinned_sync_reuses_version_cache() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+    env.cmd().args(["store", "tag", "v1.0"]).assert().success();
+    let commit = git(env.data_dir.path(), &["rev-parse", "v1.0^{commit}"]);
+
+    env.init_project();
+    env.cmd()
+        .args(["add", "my-docs", "--pin", "v1.0"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("my-docs: synced (version: v1.0)"));
+
+    let cached = env
+        .data_dir
+        .path()
+        .join("cache/versions/local")
+        .join(&commit)
+        .join("my-docs/README.md");
+    assert_eq!(std::fs::read_to_string(&cached).unwrap(), "# Sample Reference\n");
+    assert!(!env.data_dir.path().join(".tmp-version-extract").exists());
+
+    // The cache entry is what gets copied from on later syncs
+    std::fs::write(&cached, "# From cache\n").unwrap();
+    env.cmd()
+        .args(["sync", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("my-docs: synced"));
+    let synced = env.project_dir.path().join(".references/my-docs/README.md");
+    assert_eq!(std::fs::read_to_string(synced).unwrap(), "# From cache\n");
+}
+
+#[test]
+fn gc_drops_cached_versions_of_removed_references() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+    env.add_repo_ref("old-docs", &sample);
+    env.cmd().args(["store", "tag", "v1.0"]).assert().success();
+    let commit = git(env.data_dir.path(), &["rev-parse", "v1.0^{commit}"]);
+
+    env.init_project();
+    for name in ["my-docs", "old-docs"] {
+        env.cmd().args(["add", name, "--pin", "v1.0"]).assert().success();
+    }
+    env.cmd().args(["sync"]).assert().success();
+    let cached = env.data_dir.path().join("cache/versions/local").join(&commit);
+    assert!(cached.join("old-docs").exists());
+
+    env.cmd()
+        .args(["store", "remove", "old-docs", "--force"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["store", "gc"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dropped 1 cached versions of removed references."));
+    assert!(!cached.join("old-docs").exists());
+    assert!(cached.join("my-docs/README.md").exists());
+}
+
+#[test]
+fn pinned_sync_is_skipped_when_up_to_date() {
+    let env = TestEnv::new();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+    env.cmd()
+        .args(["store", "release", "my-docs", "1.0.0"])
+        .assert()
+        .success();
+
+    env.init_project();
+    env.cmd()
+        .args(["add", "my-docs", "--pin", "1.0.0"])
+        .assert()
+        .success();
+    env.cmd().args(["sync"]).assert().success();
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("my-docs: up to date"));
+
+    // A new release matching a range makes it stale again
+    env.cmd().args(["remove", "my-docs"]).assert().success();
+    env.cmd()
+        .args(["add", "my-docs", "--pin", "^1", "--include", "**/*.md"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("my-docs: synced"));
+
+    std::fs::write(sample.join("README.md"), "# Updated\n").unwrap();
+    env.cmd().args(["store", "update", "my-docs"]).assert().success();
+    env.cmd()
+        .args(["store", "release", "my-docs", "1.1.0"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("version: ^1 -> 1.1.0"));
+
+    // Deleted content is synced again even though the state says it is current
+    std::fs::remove_dir_all(env.project_dir.path().join(".references/my-docs")).unwrap();
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("my-docs: synced"));
+}
+
+#[test]
+fn pinned_sync_fails_when_reference_missing_at_version() {
+    let env = TestEnv::new();
+    env.cmd().args(["store", "tag", "empty"]).assert().success();
+    let sample = env.create_sample_files();
+    env.add_repo_ref("my-docs", &sample);
+
+    env.init_project();
+    env.cmd()
+        .args(["add", "my-docs", "--pin", "empty"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .stdout(predicate::str::contains("0 synced, 1 failed"))
+        .stderr(predicate::str::contains("failed to archive 'content/my-docs'"));
+    assert!(!env.project_dir.path().join(".references/my-docs").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn pinned_sync_rejects_symlinks_escaping_the_reference() {
+    let env = TestEnv::new();
+    let reg_dir = env.create_fake_registry(&[("remote-ref", "# v1\n")]);
+    std::os::unix::fs::symlink(
+        "../../../../etc/passwd",
+        reg_dir.join("content/remote-ref/passwd"),
+    )
+    .unwrap();
+    commit_files(&reg_dir, &[], "Add link");
+    git(&reg_dir, &["tag", "v1.0.0"]);
+    env.cmd()
+        .args(["registry", "add", "team", &format!("file://{}", reg_dir.display())])
+        .assert()
+        .success();
+
+    env.init_project();
+    env.cmd()
+        .args(["add", "remote-ref", "--pin", "v1.0.0"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["sync"])
+        .assert()
+        .stdout(predicate::str::contains("0 synced, 1 failed"))
+        .stderr(predicate::str::contains("points outside the target directory"));
+}
+
+#[cfg(unix)]
+#[test]
+fn pinned_sync_rejects_symlink_chains_escaping_the_reference() {
+    for backend in ["cli", "gix"] {
+        let env = TestEnv::new();
+        env.cmd()
+            .args(["config", "set", "git_backend", backend])
+            .assert()
+            .success();
+        let reg_dir = env.create_fake_registry(&[("remote-ref", "# v1\n")]);
+        // Each link alone stays inside; together `f` resolves to ../secret
+        let content = reg_dir.join("content/remote-ref");
+        std::fs::create_dir_all(content.join("d")).unwrap();
+        std::os::unix::fs::symlink("..", content.join("d/x")).unwrap();
+        std::os::unix::fs::symlink("d/x/../secret", content.join("f")).unwrap();
+        commit_files(&reg_dir, &[], "Add links");
+        git(&reg_dir, &["tag", "v1.0.0"]);
+        env.cmd()
+            .args(["registry", "add", "team", &format!("file://{}", reg_dir.display())])
+            .assert()
+            .success();
+
+        env.init_project();
+        env.cmd()
+            .args(["add", "remote-ref", "--pin", "v1.0.0"])
+            .assert()
+            .success();
+        env.cmd()
+            .args(["sync"])
+            .assert()
+            .stdout(predicate::str::contains("0 synced, 1 failed"))
+            .stderr(predicate::str::contains("refusing to extract 'f'"));
+        assert!(!env.project_dir.path().join(".references/remote-ref").exists(), "{backend}");
+    }
+}
@@ -26,6 +26,15 @@ impl TestEnv {
         cmd
     }
 
+    /// Like `cmd`, but against another data directory (e.g. a layer or registry being authored).
+    pub fn cmd_with_data_dir(&self, data_dir: &Path) -> Command {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("refstore"));
+        cmd.arg("--data-dir")
+            .arg(data_dir)
+            .current_dir(self.project_dir.path());
+        cmd
+    }
+
     /// Create sample files inside a subdirectory of project_dir and return that path.
     /// Structure:
     ///   sample/
@@ -160,4 +169,43 @@ path = "/fake"
 
         reg_dir
     }
+
+    /// Create a git repo at `project_dir/<name>.git` on branch `main` with `files` committed.
+    /// The `.git` suffix makes `store add` treat the path as a git source.
+    pub fn create_git_upstream(&self, name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let repo = self.project_dir.path().join(format!("{name}.git"));
+        fs::create_dir_all(&repo).unwrap();
+        git(&repo, &["init", "-b", "main"]);
+        git(&repo, &["config", "user.name", "test"]);
+        git(&repo, &["config", "user.email", "test@test"]);
+        commit_files(&repo, files, "init");
+        repo
+    }
+}
+
+/// Run a git command in `dir`, panicking on failure. Returns trimmed stdout.
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Write `files` into a git repo and commit them. Returns the new HEAD hash.
+pub fn commit_files(repo: &Path, files: &[(&str, &str)], message: &str) -> String {
+    for (path, content) in files {
+        let path = repo.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    git(repo, &["add", "-A"]);
+    git(repo, &["commit", "-m", message]);
+    git(repo, &["rev-parse", "HEAD"])
 }
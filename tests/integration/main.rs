@@ -1,9 +1,23 @@
 mod common;
 
+mod cli_build;
 mod cli_bundle;
 mod cli_config;
+mod cli_extract;
+mod cli_git;
+mod cli_git_backend;
+mod cli_history;
+mod cli_layers;
+mod cli_migrate;
+mod cli_objects;
+mod cli_outdated;
+mod cli_plugin;
 mod cli_project;
+mod cli_quota;
 mod cli_registry;
+mod cli_release;
 mod cli_repo;
+mod cli_secrets;
+mod cli_snippet;
 mod cli_sync_filter;
 mod cli_versions;